@@ -1,18 +1,68 @@
-//use std::path::Path;
-use std::env;
+use std::{env, path::Path, process, thread};
 
-use atlas::Parser;
+use atlas::{error::AtlasError, Parser, DEFAULT_MAX_DEPTH, DEFAULT_STACK_SIZE};
+
+const USAGE: &str = "Usage: atlas <file.atl>\n       atlas -e <expression>";
+
+// name used in diagnostics for the source passed with -e
+const EXPRESSION_NAME: &str = "<expression>";
+
+// the interpreter runs on a thread of its own with a larger stack than the library default
+// assumes, so programs can recurse deeper
+const STACK_SIZE: usize = 64 << 20;
+const MAX_DEPTH: usize = DEFAULT_MAX_DEPTH * (STACK_SIZE / DEFAULT_STACK_SIZE);
+
+fn run(parser: &mut Parser, args: &[String]) -> Result<(), AtlasError> {
+    match args.get(1).map(String::as_str) {
+        // evaluate the source passed as an argument, printing its value
+        Some("-e") => match args.get(2) {
+            Some(source) => {
+                parser.parse(source.clone())?;
+                if let Some(result) = parser.reduce_all()? {
+                    println!("{}", result);
+                }
+            }
+            None => {
+                eprintln!("{}", USAGE);
+                process::exit(2);
+            }
+        },
+        // scripts print what they want themselves
+        Some(path) => {
+            parser.run_file(Path::new(path))?;
+        }
+        None => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    }
+    Ok(())
+}
 
 fn main() {
+    let interpreter = thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(interpret)
+        .unwrap_or_else(|err| {
+            eprintln!("error: could not start the interpreter: {}", err);
+            process::exit(1);
+        });
+    // errors exit the process from the interpreter thread, panics are reported as usual
+    if interpreter.join().is_err() {
+        process::exit(101);
+    }
+}
+
+fn interpret() {
     let mut parser = Parser::new();
+    parser.set_max_depth(MAX_DEPTH);
     let args: Vec<String> = env::args().collect();
-    parser.parse(String::from(&args[1]));
-    parser.reduce_all();
-
-    //parser.parse(String::from(r#"(+ 1 (+ 1 1) (+ 2 2))"#));
-    // parser.reduce_all();
-    //let path = Path::new("/home/steew/Projects/local/atlas/src/bin/tests/test.atl");
-    //parser.read_file(path);
-    // TODO: fix, redundant passing of the String, ugly clone
-    //parser.parse(parser.contents.clone());
+    if let Err(err) = run(&mut parser, &args) {
+        let file_name = match args.get(1).map(String::as_str) {
+            Some("-e") | None => EXPRESSION_NAME,
+            Some(path) => path,
+        };
+        eprint!("{}", parser.render_error(&err, file_name));
+        process::exit(1);
+    }
 }
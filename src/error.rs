@@ -0,0 +1,337 @@
+//! This module holds the errors that can happen while parsing or reducing.
+
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+    io,
+};
+
+use crate::tree::{
+    lexer::{is_line_terminator, Span},
+    Token,
+};
+
+/// Everything that can go wrong in Atlas. Errors caused by some source code carry the span of
+/// the innermost expression they happened in, when it is known.
+#[derive(Debug)]
+pub enum AtlasError {
+    /// The source code is not well formed.
+    Parse { message: String, span: Option<Span> },
+    /// A name has no value bound to it.
+    UnboundVariable { name: String, span: Option<Span> },
+    /// A value of the wrong type was passed, optionally as a known argument of a function.
+    Type {
+        expected: String,
+        found: String,
+        argument: Option<String>,
+        span: Option<Span>,
+    },
+    /// A function or special form got the wrong number of arguments.
+    Arity {
+        name: String,
+        expected: String,
+        found: usize,
+        span: Option<Span>,
+    },
+    /// Expressions were nested deeper than the limit, either in the source or while reducing.
+    RecursionLimit { limit: usize, span: Option<Span> },
+    /// Reading a file or writing to the output failed.
+    Io(io::Error),
+    /// Anything else that fails while reducing.
+    Runtime { message: String, span: Option<Span> },
+}
+
+impl AtlasError {
+    /// Create a parse error.
+    pub fn parse(message: impl Into<String>) -> Self {
+        AtlasError::Parse {
+            message: message.into(),
+            span: None,
+        }
+    }
+    /// Create an unbound variable error.
+    pub fn unbound(name: impl Into<String>) -> Self {
+        AtlasError::UnboundVariable {
+            name: name.into(),
+            span: None,
+        }
+    }
+    /// Create a type error, describing what was expected and the value found instead.
+    pub fn type_error(expected: impl Into<String>, found: &Token) -> Self {
+        let found = match found {
+            // nested expressions would make the message too long
+            Token::Expression(_) => found.type_name().to_string(),
+            _ => format!("{} `{}`", found.type_name(), found),
+        };
+        AtlasError::Type {
+            expected: expected.into(),
+            found,
+            argument: None,
+            span: None,
+        }
+    }
+    /// Point a type error at the argument of a function, counting from 1, that caused it.
+    pub fn for_argument(mut self, function: &str, position: usize) -> Self {
+        if let AtlasError::Type { argument, .. } = &mut self {
+            *argument = Some(format!("argument {} of `{}`", position, function));
+        }
+        self
+    }
+    /// Create an arity error. The expected count is written for humans, as in "at least 1".
+    pub fn arity(name: impl Into<String>, expected: impl Into<String>, found: usize) -> Self {
+        AtlasError::Arity {
+            name: name.into(),
+            expected: expected.into(),
+            found,
+            span: None,
+        }
+    }
+    /// Create an error for going past the given recursion depth limit.
+    pub fn recursion_limit(limit: usize) -> Self {
+        AtlasError::RecursionLimit { limit, span: None }
+    }
+    /// Create a runtime error.
+    pub fn runtime(message: impl Into<String>) -> Self {
+        AtlasError::Runtime {
+            message: message.into(),
+            span: None,
+        }
+    }
+    /// Get the span of the source code this error happened in.
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            AtlasError::Parse { span, .. }
+            | AtlasError::UnboundVariable { span, .. }
+            | AtlasError::Type { span, .. }
+            | AtlasError::Arity { span, .. }
+            | AtlasError::RecursionLimit { span, .. }
+            | AtlasError::Runtime { span, .. } => *span,
+            AtlasError::Io(_) => None,
+        }
+    }
+    /// Move the span of this error forward, as when its source is part of a larger one.
+    pub fn offset_span(mut self, offset: usize) -> Self {
+        match &mut self {
+            AtlasError::Parse { span, .. }
+            | AtlasError::UnboundVariable { span, .. }
+            | AtlasError::Type { span, .. }
+            | AtlasError::Arity { span, .. }
+            | AtlasError::RecursionLimit { span, .. }
+            | AtlasError::Runtime { span, .. } => {
+                if let Some(span) = span {
+                    *span = (span.0 + offset, span.1 + offset);
+                }
+            }
+            AtlasError::Io(_) => (),
+        }
+        self
+    }
+    /// Set the span of this error, unless a more precise one was already set.
+    pub fn with_span(mut self, new_span: Span) -> Self {
+        match &mut self {
+            AtlasError::Parse { span, .. }
+            | AtlasError::UnboundVariable { span, .. }
+            | AtlasError::Type { span, .. }
+            | AtlasError::Arity { span, .. }
+            | AtlasError::RecursionLimit { span, .. }
+            | AtlasError::Runtime { span, .. } => {
+                span.get_or_insert(new_span);
+            }
+            AtlasError::Io(_) => (),
+        }
+        self
+    }
+}
+
+impl AtlasError {
+    /// Get the headline of this error, without the details that go in the notes.
+    pub fn summary(&self) -> String {
+        match self {
+            AtlasError::Type {
+                argument: Some(argument),
+                ..
+            } => format!("mismatched types in {}", argument),
+            AtlasError::Type { .. } => String::from("mismatched types"),
+            AtlasError::Arity { name, .. } => {
+                format!("wrong number of arguments passed to `{}`", name)
+            }
+            _ => self.to_string(),
+        }
+    }
+    /// Get the notes explaining this error, if there are any.
+    pub fn notes(&self) -> Vec<String> {
+        match self {
+            AtlasError::Type {
+                expected, found, ..
+            } => vec![format!("expected {}, found {}", expected, found)],
+            AtlasError::Arity {
+                expected, found, ..
+            } => vec![format!(
+                "expected {} argument{}, found {}",
+                expected,
+                plural(expected),
+                found
+            )],
+            AtlasError::RecursionLimit { limit, .. } => vec![format!(
+                "expressions can be nested at most {} levels deep",
+                limit
+            )],
+            _ => Vec::new(),
+        }
+    }
+    /// Render this error the way rustc does, pointing at the source code it happened in:
+    ///
+    /// ```text
+    /// error: mismatched types in argument 2 of `+`
+    ///  --> test.atl:1:1
+    ///   |
+    /// 1 | (+ 1 "a")
+    ///   | ^^^^^^^^^
+    ///   = note: expected a number, found string literal `"a"`
+    /// ```
+    pub fn render(&self, source: &str, file_name: &str) -> String {
+        self.render_at(source, 0, file_name)
+    }
+    /// Render this error against source code found at the given byte offset of the code its
+    /// span points into. Spans that don't fall inside the source are left out of the rendering.
+    pub fn render_at(&self, source: &str, offset: usize, file_name: &str) -> String {
+        let mut rendered = format!("error: {}\n", self.summary());
+        let span = self
+            .span()
+            .and_then(|span| Some((span.0.checked_sub(offset)?, span.1.checked_sub(offset)?)))
+            // is_char_boundary is false past the end too
+            .filter(|span| {
+                span.0 <= span.1
+                    && source.is_char_boundary(span.0)
+                    && source.is_char_boundary(span.1)
+            });
+        let span = match span {
+            Some(span) => span,
+            None => {
+                rendered.push_str(&format!(" --> {}\n", file_name));
+                for note in self.notes() {
+                    rendered.push_str(&format!(" = note: {}\n", note));
+                }
+                return rendered;
+            }
+        };
+        let (line, column) = line_column(source, span.0);
+        let (_, line_start) = line_start(source, span.0);
+        let line_end = source[line_start..]
+            .find(is_line_terminator)
+            .map_or(source.len(), |end| line_start + end);
+        let text = &source[line_start..line_end];
+        // spans going past the end of the line are only underlined up to it
+        let end = span.1.clamp(span.0, line_start + text.len());
+        let padding = " ".repeat(display_width(&source[line_start..span.0]));
+        let underline = "^".repeat(display_width(&source[span.0..end]).max(1));
+        let gutter = " ".repeat(line.to_string().len());
+        rendered.push_str(&format!(
+            "{}--> {}:{}:{}\n",
+            gutter, file_name, line, column
+        ));
+        rendered.push_str(&format!("{} |\n", gutter));
+        rendered.push_str(&format!("{} | {}\n", line, text.replace('\t', "    ")));
+        rendered.push_str(&format!("{} | {}{}\n", gutter, padding, underline));
+        for note in self.notes() {
+            rendered.push_str(&format!("{} = note: {}\n", gutter, note));
+        }
+        rendered
+    }
+}
+
+// suffix for "argument" after the expected count, as in "1 argument" or "at least 2 arguments".
+fn plural(expected: &str) -> &'static str {
+    if expected == "1" || expected.ends_with(" 1") {
+        ""
+    } else {
+        "s"
+    }
+}
+
+/// Get the line and column, both starting at 1, of a byte offset in the source. Columns are
+/// counted in characters. Offsets past the end or inside a character count as the start of the
+/// character before them. Lines end the same way the lexer ends them, see is_line_terminator.
+pub fn line_column(source: &str, offset: usize) -> (usize, usize) {
+    let mut offset = offset.min(source.len());
+    while !source.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    let (line, line_start) = line_start(source, offset);
+    (line, source[line_start..offset].chars().count() + 1)
+}
+
+// get the line, starting at 1, of a byte offset on a character boundary, and the offset that
+// line starts at. "\r\n" ends a single line.
+fn line_start(source: &str, offset: usize) -> (usize, usize) {
+    let mut line = 1;
+    let mut start = 0;
+    let mut chars = source[..offset].char_indices().peekable();
+    while let Some((index, char)) = chars.next() {
+        if is_line_terminator(char) {
+            let end = match chars.next_if(|&(_, next)| char == '\r' && next == '\n') {
+                Some((newline, _)) => newline + 1,
+                None => index + char.len_utf8(),
+            };
+            line += 1;
+            start = end;
+        }
+    }
+    (line, start)
+}
+
+// width of some text once printed, with tabs expanded as the rendered source line does.
+fn display_width(text: &str) -> usize {
+    text.chars()
+        .map(|char| if char == '\t' { 4 } else { 1 })
+        .sum()
+}
+
+impl Display for AtlasError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            AtlasError::Parse { message, .. } => write!(f, "{}", message),
+            AtlasError::UnboundVariable { name, .. } => write!(f, "unbound variable `{}`", name),
+            AtlasError::Type {
+                expected, found, ..
+            } => write!(
+                f,
+                "{}: expected {}, found {}",
+                self.summary(),
+                expected,
+                found
+            ),
+            AtlasError::Arity {
+                name,
+                expected,
+                found,
+                ..
+            } => write!(
+                f,
+                "`{}` expects {} argument{}, found {}",
+                name,
+                expected,
+                plural(expected),
+                found
+            ),
+            AtlasError::RecursionLimit { .. } => write!(f, "maximum recursion depth exceeded"),
+            AtlasError::Io(err) => write!(f, "{}", err),
+            AtlasError::Runtime { message, .. } => write!(f, "{}", message),
+        }
+    }
+}
+
+impl Error for AtlasError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            AtlasError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for AtlasError {
+    fn from(err: io::Error) -> Self {
+        AtlasError::Io(err)
+    }
+}
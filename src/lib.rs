@@ -1,248 +1,141 @@
-use std::{collections::HashMap, path::Path};
+use std::{
+    io::{self, Write},
+    path::Path,
+};
 
-use tree::{Symbols, TokenExpression, Tree};
+use error::AtlasError;
+use logic::env::{Environment, Output};
+use tree::{reader::Reader, Tree};
 
 use crate::tree::Token;
 
+/// This module holds the errors that can happen while parsing or reducing.
+pub mod error;
 /// This module contains the language logic. Operators, functions, et cetera.
 pub mod logic;
 /// This module holds the syntax that composes the language, such as its parsed tree.
 pub mod tree;
 
+/// How deeply expressions can be nested by default, both in the source and while reducing.
+/// Every call that is not in tail position nests one more level, as does every list in a
+/// quasiquote template.
+///
+/// It is low enough for the 2 MiB stack `std::thread::spawn` gives new threads, even in debug
+/// builds, where every level takes a few kilobytes. Parsers running on larger stacks can raise
+/// it in proportion with Parser::set_max_depth.
+pub const DEFAULT_MAX_DEPTH: usize = 256;
+
+/// The stack size DEFAULT_MAX_DEPTH is safe for.
+pub const DEFAULT_STACK_SIZE: usize = 2 << 20;
+
 pub struct Parser {
     tree: Tree,
+    // every source code parsed so far, one after the other, so functions defined by earlier
+    // ones can still point at their code in errors
     contents: String,
-    current_depth: usize,
-    // vector with last index used in this depth
-    // represents how many arguments (tokens) have been provided in each depth. As the vector
-    // is ordered, to get the arguments of an specific depth, just depth_argument_len.get(depth)
-    n_expressions_in_depth: Vec<usize>,
+    // where each parsed source code starts in the contents, in order
+    starts: Vec<usize>,
+    // global environment, shared by every top level expression
+    env: Environment,
+    // how deeply forms can be nested in the source
+    max_depth: usize,
     // etc.
 }
 
+impl Default for Parser {
+    fn default() -> Self {
+        Parser::new()
+    }
+}
+
+// functions defined at the top level are stored in the global frame and hold on to it, so the
+// frame is cleared to free them. Functions bound by letrec hold their own frame the same way,
+// and may still be alive, so the output is replaced too, which lets the writer be dropped.
+impl Drop for Parser {
+    fn drop(&mut self) {
+        self.env.clear();
+        self.env.set_output(Output::new(io::sink()));
+    }
+}
+
 impl Parser {
     // create a new parser instance
     pub fn new() -> Self {
         Parser {
             tree: Tree::new(),
             contents: String::new(),
-            current_depth: 0,
-            n_expressions_in_depth: Vec::new(),
+            starts: Vec::new(),
+            env: Environment::new(),
+            max_depth: DEFAULT_MAX_DEPTH,
         }
     }
-    //
-    pub fn reduce_all(&mut self) {
-        // get the lowest depth
-        // HashMap<(depth, index), Token>
-        let mut results_map: HashMap<(usize, usize), Token> = HashMap::new();
-        // sort the expressions by depth (descending)
-        self.tree
-            .expressions
-            .sort_unstable_by(|exp1, exp2| exp1.depth.cmp(&exp2.depth).reverse());
-        // println!("{:?}", self.tree);
-        // iterate the re-ordered vector of expressions, and store their result in a hash map.
-        for exp in self.tree.expressions.iter_mut() {
-            if !exp.args.is_empty() {
-                for arg in exp.args.iter_mut() {
-                    match arg {
-                        Token::Expression((depth, index)) => {
-                            if let Some(val_to_update) = results_map.get_mut(&(*depth + 1, *index))
-                            {
-                                // print!("Replaced {:?}", arg);
-                                let _ = std::mem::replace(arg, val_to_update.clone());
-                                // println!(" with {:?}", arg);
-                            }
-                        }
-                        _ => continue,
-                    }
-                }
-            }
-            // println!("-------------");
-            // println!("Reducing: {:?}", exp);
-            let result = exp.reduce();
-            println!("Result: {:?}", result);
-            results_map.insert((exp.depth, exp.index), result);
-        }
+    /// Change how deeply expressions can be nested, both in the source and while reducing, as
+    /// in non tail recursive calls. Going past it fails with AtlasError::RecursionLimit instead
+    /// of overflowing the stack, as long as the stack is large enough for it.
+    /// Defaults to DEFAULT_MAX_DEPTH, which is safe for stacks of DEFAULT_STACK_SIZE.
+    pub fn set_max_depth(&mut self, limit: usize) {
+        self.max_depth = limit;
+        self.env.set_max_depth(limit);
     }
-    // get an expression from depth and index.
-    pub fn get_from_depth_and_idx(&mut self, depth: usize, idx: usize) -> &TokenExpression {
-        let old_depth = self.current_depth;
-        self.current_depth = depth;
-        let te = &self.tree.expressions[self.calculate_index(idx)];
-        self.current_depth = old_depth;
-        te
+    /// Change where print, display and newline write to. Defaults to stdout.
+    pub fn set_output(&mut self, writer: impl Write + 'static) {
+        self.env.set_output(Output::new(writer));
     }
-
-    fn get_last(&self) -> &TokenExpression {
-        let depth = self.n_expressions_in_depth[self.current_depth];
-        if depth == 0 {
-            self.tree
-                .peek(self.calculate_index(self.n_expressions_in_depth[self.current_depth]))
-        } else {
-            self.tree
-                .peek(self.calculate_index(self.n_expressions_in_depth[self.current_depth] - 1))
+    /// Reduce every top level form, in the order they were written.
+    /// Returns the value of the last one, if there is any.
+    pub fn reduce_all(&mut self) -> Result<Option<Token>, AtlasError> {
+        let mut result = None;
+        for form in self.tree.iter() {
+            // nested expressions are reduced on demand by their parent
+            result = Some(form.reduce(&self.env)?);
         }
+        Ok(result)
     }
-    fn get_last_mut(&mut self) -> &mut TokenExpression {
-        let depth = self.n_expressions_in_depth[self.current_depth];
-        if depth == 0 {
-            self.tree
-                .peek_mut(self.calculate_index(self.n_expressions_in_depth[self.current_depth]))
-        } else {
-            self.tree
-                .peek_mut(self.calculate_index(self.n_expressions_in_depth[self.current_depth] - 1))
-        }
+    /// Read file and parse its contents, replacing the previous tree.
+    pub fn read_file(&mut self, path: &Path) -> Result<(), AtlasError> {
+        let bytes = std::fs::read(path)?;
+        let string = String::from_utf8(bytes)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        self.parse(string)
     }
-    // This function calculates the access index for an element at (depth, index). This is neccessary
-    // because for optimization reasons, we store our expressions in a single Vec<> instead of Vec<Vec<>>, which
-    // would consist of a vector of depths with each depth having multiple TokenExpressions. As it is stored in a single
-    // vec, we could have something like this: [Depth0Idx0, Depth0Idx1, Depth1Idx0], which means that we need to calculate
-    // the correct index to access Depth1Idx0, instead of using index 1.
-    // This function gets the number of arguments per depth, which is stored
-    // in n_expressions_in_depth as a vector. Each index corresponds to a depth and the value corresponds to the amount
-    // of TokenExpressions that are in that depth. So, to get Depth1Idx0 we should access (Depth0args + Depth1Idx) - 1 =
-    // (2 + 1) - 1 = 2, the third element in the self.tree.expressions vector.
-    fn calculate_index(&self, index: usize) -> usize {
-        let mut idx_sum: usize = 0;
-        for (cur_depth, lens) in self.n_expressions_in_depth.clone().into_iter().enumerate() {
-            if cur_depth == self.current_depth {
-                // if we are in the desired depth, only add up to the required index, and stop adding
-                idx_sum += index;
-                break;
-            } else {
-                // else if we are in the previous depths, we need to sum the entire length of the vector.
-                idx_sum += lens;
-            }
-        }
-        // index starts at 0
-        /*         if idx_sum > 0 {
-            idx_sum -= 1;
-        } */
-        idx_sum
+    /// Read, parse and reduce a whole file, returning the value of its last form.
+    pub fn run_file(&mut self, path: &Path) -> Result<Option<Token>, AtlasError> {
+        self.read_file(path)?;
+        self.reduce_all()
     }
 
-    /// Read file and send it to a string
-    pub fn read_file(&mut self, path: &Path) {
-        let bytes = std::fs::read(path).unwrap();
-        let string = String::from_utf8(bytes).unwrap();
-        self.contents = string;
+    /// Render an error returned by this parser, pointing at the source code it happened in.
+    /// That is the last parsed one, unless the error happened in a function defined by an
+    /// earlier one.
+    pub fn render_error(&self, err: &AtlasError, file_name: &str) -> String {
+        let index = match err.span() {
+            Some(span) => self.starts.partition_point(|start| *start <= span.0),
+            None => self.starts.len(),
+        };
+        let start = index
+            .checked_sub(1)
+            .and_then(|index| self.starts.get(index))
+            .copied()
+            .unwrap_or(0);
+        let end = self
+            .starts
+            .get(index)
+            .copied()
+            .unwrap_or(self.contents.len());
+        err.render_at(&self.contents[start..end], start, file_name)
     }
-
-    pub fn parse(&mut self, contents: String) {
-        self.contents = contents.clone();
-        println!("Parsing: {}", self.contents);
-        /* for (i, c) in self.contents.chars().into_iter().enumerate() {
-            print!("{}:{} ", i, c);
-        }
-        println!(""); */
-        println!("");
-        let mut currently_parsing_token: String = String::new();
-        let char_iterator = contents.chars();
-        // start simple, by finding the first TokenExpression
-        // add first TokenExpression
-        self.tree.push(TokenExpression::new());
-        self.n_expressions_in_depth.push(0);
-        for (index, char) in char_iterator.into_iter().enumerate() {
-            // println!("Parsing char: {} from depth: {}", char, self.current_depth);
-            // try to get a known symboindex l from this char
-            if let Ok(sym) = Symbols::try_from(char) {
-                match sym {
-                    // check for opening paren if we closed all previous expressions
-                    Symbols::RPAREN => {
-                        // special case where the expression ends, but we haven't stored the
-                        // last parsed token yet. NOTE: we don't do this with LPAREN, cause the syntax
-                        // token(expression), without a space between them, is considered incorrect.
-                        if !currently_parsing_token.is_empty() {
-                            self.get_last_mut()
-                                .args
-                                .push(Token::from(currently_parsing_token.clone()));
-                            currently_parsing_token.clear();
-                        }
-                        // end token parsing
-                        let current_expr = self.get_last_mut();
-                        if current_expr.is_unclosed() {
-                            current_expr.insert_closing(index);
-                            if self.current_depth > 0 {
-                                // go one expression up
-                                self.current_depth -= 1;
-                            }
-                        } else {
-                            if self.current_depth > 0 {
-                                // go one expression up
-                                self.current_depth -= 1;
-                            }
-                            let idx = self.calculate_index(0);
-                            let current_expr = self.tree.peek_mut(idx);
-                            current_expr.insert_closing(index);
-                        }
-                    }
-                    //Symbols::LPAREN if self.get_last().is_unclosed() == true => {
-                    Symbols::LPAREN => {
-                        if self.get_last().get_opening().is_none() {
-                            let current_expr = self.get_last_mut();
-                            current_expr.insert_opening(index);
-                            continue;
-                        }
-                        // create a new expression and leave the other in the stack
-                        self.tree.push(TokenExpression::new());
-                        // increment this depth's index, otherwise, push a new depth to the vector
-                        match self.n_expressions_in_depth.get(self.current_depth) {
-                            Some(len) => {
-                                self.n_expressions_in_depth
-                                    .insert(self.current_depth, len + 1);
-                            }
-                            None => {
-                                self.n_expressions_in_depth.push(0);
-                                //println!("Inserting index: 1 in depth: {}", self.current_depth);
-                            }
-                        }
-                        let depth_index = self.n_expressions_in_depth[self.current_depth];
-                        self.tree
-                            .peek_mut(self.current_depth)
-                            .args
-                            .push(Token::Expression((
-                                self.current_depth,
-                                self.n_expressions_in_depth[self.current_depth],
-                            )));
-                        self.current_depth += 1;
-
-                        // update the reference
-                        let current_expr = self.get_last_mut();
-                        current_expr.insert_opening(index);
-                        current_expr.index = depth_index;
-                        // HACK: workaround for mut & immutable references at the same time
-                        #[allow(unused_variables)]
-                        let current_expr = ();
-                        let depth = self.current_depth;
-                        let current_expr = self.get_last_mut();
-                        current_expr.depth = depth;
-                        // END HACK
-                    }
-                }
-            } else {
-                currently_parsing_token.push(char);
-                // If we reached a space, push the parsed token to the current expression
-                if char == ' ' && self.get_last().keyword.is_some() {
-                    // remove the end space
-                    currently_parsing_token.pop();
-                    if !currently_parsing_token.is_empty() {
-                        self.get_last_mut()
-                            .args
-                            .push(Token::from(currently_parsing_token.clone()));
-                    }
-                    currently_parsing_token.clear();
-                // if we had no keyword, store it
-                } else if char == ' ' && self.get_last().keyword.is_none() {
-                    if !currently_parsing_token.is_empty() {
-                        currently_parsing_token.pop();
-                        self.get_last_mut().keyword =
-                            Some(Token::from(currently_parsing_token.clone()));
-                    }
-                    currently_parsing_token.clear();
-                }
-            }
-        }
-        // finished iterating, check if it's closed
-        //println!("{:?}", self.tree);
+    /// Parse the source code into a new tree, replacing the previous one. Everything defined
+    /// while reducing the previous tree is kept, and so is its source code. If reading fails, the
+    /// tree is left empty, so the previous one doesn't run again.
+    pub fn parse(&mut self, contents: String) -> Result<(), AtlasError> {
+        // keep the contents even if reading fails, so errors can point at them
+        self.tree = Tree::new();
+        let start = self.contents.len();
+        self.contents.push_str(&contents);
+        self.starts.push(start);
+        let mut reader = Reader::new_at(&self.contents, start);
+        reader.set_max_depth(self.max_depth);
+        self.tree = reader.read_tree()?;
+        Ok(())
     }
 }
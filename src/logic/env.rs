@@ -0,0 +1,230 @@
+//! This module holds the environments where variables and user defined functions are stored.
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    fmt::{self, Debug, Formatter},
+    io::{self, Write},
+    mem,
+    rc::Rc,
+};
+
+use crate::{error::AtlasError, tree::Token, DEFAULT_MAX_DEPTH};
+
+/// A function defined by the user through `defun` or `lambda`.
+pub struct Function {
+    // Name the function was defined with, None for lambdas
+    pub name: Option<String>,
+    // Names of the parameters, in the order the arguments have to be passed
+    pub params: Vec<String>,
+    // Expressions evaluated in order when the function is called. The last one is the result.
+    pub body: Vec<Token>,
+    // Environment the function was defined in, which its calls are nested in
+    pub env: Environment,
+}
+
+impl Function {
+    /// Get the name this function is called by in error messages.
+    pub fn name(&self) -> &str {
+        self.name.as_deref().unwrap_or("lambda")
+    }
+}
+
+// the captured environment is left out, as it usually holds the function itself
+impl Debug for Function {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Function")
+            .field("name", &self.name)
+            .field("params", &self.params)
+            .field("body", &self.body)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A single scope. Names not found here are looked up in the parent frame.
+#[derive(Debug, Default)]
+struct Frame {
+    variables: HashMap<String, Token>,
+    parent: Option<Environment>,
+}
+
+// long chains of frames, as built by let*, would otherwise be dropped recursively, one stack
+// frame per frame, so the parents only used by this one are taken apart in a loop instead.
+impl Drop for Frame {
+    fn drop(&mut self) {
+        let mut parent = self.parent.take();
+        while let Some(env) = parent {
+            // frames still used by another environment are left to it
+            parent = match Rc::try_unwrap(env.frame) {
+                Ok(frame) => frame.into_inner().parent.take(),
+                Err(_) => None,
+            };
+        }
+    }
+}
+
+/// Where printing functions write to. Shared by every frame of an environment.
+#[derive(Clone)]
+pub struct Output(Rc<RefCell<dyn Write>>);
+
+impl Output {
+    /// Wrap any writer, such as a file or a Vec<u8> buffer, to be used as the output.
+    pub fn new(writer: impl Write + 'static) -> Self {
+        Output(Rc::new(RefCell::new(writer)))
+    }
+    /// Write a string to the output.
+    pub fn write(&self, string: &str) -> Result<(), AtlasError> {
+        Ok(self.0.borrow_mut().write_all(string.as_bytes())?)
+    }
+    /// Make sure everything written so far reaches its destination.
+    pub fn flush(&self) -> Result<(), AtlasError> {
+        Ok(self.0.borrow_mut().flush()?)
+    }
+}
+
+impl Default for Output {
+    fn default() -> Self {
+        Output::new(io::stdout())
+    }
+}
+
+impl Debug for Output {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Output")
+    }
+}
+
+/// Counts how deeply expressions are nested while reducing, so going too deep fails before the
+/// stack overflows. Shared by every frame of an environment.
+#[derive(Debug)]
+struct Depth {
+    current: Cell<usize>,
+    limit: Cell<usize>,
+}
+
+impl Default for Depth {
+    fn default() -> Self {
+        Depth {
+            current: Cell::new(0),
+            limit: Cell::new(DEFAULT_MAX_DEPTH),
+        }
+    }
+}
+
+/// Marks that an expression is being reduced. Dropping it leaves the expression.
+pub struct DepthGuard<'a>(&'a Depth);
+
+impl Drop for DepthGuard<'_> {
+    fn drop(&mut self) {
+        self.0.current.set(self.0.current.get() - 1);
+    }
+}
+
+/// Holds everything the user has defined, so it can be used from later expressions. Functions
+/// are values like any other, so they share the same names as variables.
+/// Cloning an environment is cheap, both clones refer to the same frame.
+#[derive(Debug, Default, Clone)]
+pub struct Environment {
+    frame: Rc<RefCell<Frame>>,
+    output: Rc<RefCell<Output>>,
+    depth: Rc<Depth>,
+}
+
+impl Environment {
+    /// Create a new, empty global environment, printing to stdout.
+    pub fn new() -> Self {
+        Environment {
+            frame: Rc::new(RefCell::new(Frame::default())),
+            output: Rc::default(),
+            depth: Rc::default(),
+        }
+    }
+    /// Create a new frame nested in this one, as used by function calls and `let`.
+    pub fn extend(&self) -> Self {
+        Environment {
+            frame: Rc::new(RefCell::new(Frame {
+                variables: HashMap::new(),
+                parent: Some(self.clone()),
+            })),
+            output: self.output.clone(),
+            depth: self.depth.clone(),
+        }
+    }
+    /// Get where printing functions write to.
+    pub fn output(&self) -> Output {
+        self.output.borrow().clone()
+    }
+    /// Change where printing functions write to. Applies to every frame, including the ones
+    /// captured by functions defined earlier.
+    pub fn set_output(&self, output: Output) {
+        *self.output.borrow_mut() = output;
+    }
+    /// Change how deeply expressions can be nested while reducing. Applies to every frame.
+    pub fn set_max_depth(&self, limit: usize) {
+        self.depth.limit.set(limit);
+    }
+    /// Enter a nested expression, failing if that goes past the depth limit.
+    /// The expression is left when the returned guard is dropped.
+    pub fn enter(&self) -> Result<DepthGuard<'_>, AtlasError> {
+        let limit = self.depth.limit.get();
+        if self.depth.current.get() >= limit {
+            return Err(AtlasError::recursion_limit(limit));
+        }
+        self.depth.current.set(self.depth.current.get() + 1);
+        Ok(DepthGuard(&self.depth))
+    }
+    /// Get the outermost frame of this environment.
+    pub fn global(&self) -> Self {
+        let mut env = self.clone();
+        loop {
+            let parent = env.frame.borrow().parent.clone();
+            match parent {
+                Some(parent) => env = parent,
+                None => return env,
+            }
+        }
+    }
+    /// Bind a variable in this frame, shadowing any other one with the same name in the parents.
+    pub fn define(&self, name: &str, value: Token) {
+        self.frame
+            .borrow_mut()
+            .variables
+            .insert(name.to_string(), value);
+    }
+    /// Forget every variable bound in this frame. Functions defined in a frame hold on to it,
+    /// so this is what lets a frame holding some be freed.
+    pub fn clear(&self) {
+        // the variables are dropped once the frame is no longer borrowed
+        let variables = mem::take(&mut self.frame.borrow_mut().variables);
+        drop(variables);
+    }
+    /// Update the innermost binding of an already defined variable.
+    pub fn set(&self, name: &str, value: Token) -> Result<(), AtlasError> {
+        let mut value = Some(value);
+        self.find(|frame| {
+            let variable = frame.variables.get_mut(name)?;
+            *variable = value.take()?;
+            Some(())
+        })
+        .ok_or_else(|| AtlasError::unbound(name))
+    }
+    /// Resolve a variable to its value, looking from the innermost frame outwards.
+    pub fn get(&self, name: &str) -> Option<Token> {
+        self.find(|frame| frame.variables.get(name).cloned())
+    }
+    // look through the frames from this one outwards, in a loop so chains of frames can be as
+    // long as memory allows, until one of them gives a result.
+    fn find<T>(&self, mut look: impl FnMut(&mut Frame) -> Option<T>) -> Option<T> {
+        let mut frame = self.frame.clone();
+        loop {
+            let parent = {
+                let mut current = frame.borrow_mut();
+                if let Some(found) = look(&mut current) {
+                    return Some(found);
+                }
+                current.parent.as_ref()?.frame.clone()
+            };
+            frame = parent;
+        }
+    }
+}
@@ -0,0 +1,417 @@
+//! This module holds the special forms. Unlike functions, they receive their arguments
+//! unreduced, and decide themselves which ones to reduce and in what environment.
+
+use crate::{
+    error::AtlasError,
+    tree::{symbol::Symbol, Token, TokenExpression},
+};
+
+use std::rc::Rc;
+
+use super::{
+    env::{Environment, Function},
+    reduce_body, reduce_token, Callable, Step,
+};
+
+/// Hold the language special forms.
+pub enum SpecialForms {
+    Begin,
+    Defun,
+    Lambda,
+    Define,
+    Set,
+    Let,
+    LetStar,
+    Letrec,
+    If,
+    Cond,
+    When,
+    Unless,
+    And,
+    Or,
+    Quote,
+    Quasiquote,
+    Unquote,
+    UnquoteSplicing,
+}
+
+impl SpecialForms {
+    // reduce the unreduced expression, leaving the token in tail position, if any, to the caller.
+    pub fn exec(&self, exp: &TokenExpression, env: &Environment) -> Result<Step, AtlasError> {
+        match self {
+            SpecialForms::Begin => reduce_body(&exp.args, env),
+            SpecialForms::Defun => defun(exp, env).map(Step::Done),
+            SpecialForms::Lambda => lambda(exp, env).map(Step::Done),
+            SpecialForms::Define => define(exp, env).map(Step::Done),
+            SpecialForms::Set => set(exp, env).map(Step::Done),
+            SpecialForms::Let => let_(exp, env),
+            SpecialForms::LetStar => let_star(exp, env),
+            SpecialForms::Letrec => letrec(exp, env),
+            SpecialForms::If => if_(exp, env),
+            SpecialForms::Cond => cond(exp, env),
+            SpecialForms::When => when(exp, env, true),
+            SpecialForms::Unless => when(exp, env, false),
+            SpecialForms::And => and(exp, env),
+            SpecialForms::Or => or(exp, env),
+            SpecialForms::Quote => match exp.args.as_slice() {
+                [datum] => Ok(Step::Done(self::datum(datum))),
+                _ => Err(AtlasError::arity("quote", "1", exp.args.len())),
+            },
+            SpecialForms::Quasiquote => match exp.args.as_slice() {
+                [template] => quasiquote(template, env, 0).map(Step::Done),
+                _ => Err(AtlasError::arity("quasiquote", "1", exp.args.len())),
+            },
+            SpecialForms::Unquote => Err(AtlasError::parse("unquote used outside of a quasiquote")),
+            SpecialForms::UnquoteSplicing => Err(AtlasError::parse(
+                "unquote-splicing used outside of a quasiquote",
+            )),
+        }
+    }
+}
+
+impl TryFrom<&Token> for SpecialForms {
+    type Error = &'static str;
+    fn try_from(value: &Token) -> Result<Self, Self::Error> {
+        match value {
+            Token::Variable(name) => match name.to_lowercase().trim() {
+                "begin" => Ok(SpecialForms::Begin),
+                "defun" => Ok(SpecialForms::Defun),
+                "lambda" => Ok(SpecialForms::Lambda),
+                "define" => Ok(SpecialForms::Define),
+                "set!" => Ok(SpecialForms::Set),
+                "let" => Ok(SpecialForms::Let),
+                "let*" => Ok(SpecialForms::LetStar),
+                "letrec" => Ok(SpecialForms::Letrec),
+                "if" => Ok(SpecialForms::If),
+                "cond" => Ok(SpecialForms::Cond),
+                "when" => Ok(SpecialForms::When),
+                "unless" => Ok(SpecialForms::Unless),
+                "and" => Ok(SpecialForms::And),
+                "or" => Ok(SpecialForms::Or),
+                "quote" => Ok(SpecialForms::Quote),
+                "quasiquote" => Ok(SpecialForms::Quasiquote),
+                "unquote" => Ok(SpecialForms::Unquote),
+                "unquote-splicing" => Ok(SpecialForms::UnquoteSplicing),
+                _ => Err("Unknown special form passed."),
+            },
+            _ => Err("Unknown special form passed."),
+        }
+    }
+}
+
+/// Get the expression a token holds, failing with the given error if it is not one.
+fn expression<'a>(
+    token: Option<&'a Token>,
+    err: &'static str,
+) -> Result<&'a TokenExpression, AtlasError> {
+    match token {
+        Some(Token::Expression(exp)) => Ok(exp),
+        _ => Err(AtlasError::parse(err)),
+    }
+}
+
+/// Get the name a token holds, failing with the given error if it is not a variable.
+fn name(token: Option<&Token>, err: &'static str) -> Result<String, AtlasError> {
+    match token {
+        Some(Token::Variable(name)) => Ok(name.clone()),
+        _ => Err(AtlasError::parse(err)),
+    }
+}
+
+/// Create a procedure out of a parameter list and a body, capturing the environment it is
+/// created in.
+fn procedure(
+    name: Option<String>,
+    params: Option<&Token>,
+    body: &[Token],
+    env: &Environment,
+) -> Result<Token, AtlasError> {
+    let form = if name.is_some() { "defun" } else { "lambda" };
+    let params = match params {
+        Some(Token::Expression(params)) => params
+            .items()
+            .map(|param| self::name(Some(param), "parameters must be names"))
+            .collect::<Result<Vec<String>, AtlasError>>()?,
+        _ => {
+            return Err(AtlasError::parse(format!(
+                "{} expects a parameter list",
+                form
+            )))
+        }
+    };
+    Ok(Token::Procedure(Callable::User(Rc::new(Function {
+        name,
+        params,
+        body: body.to_vec(),
+        env: env.clone(),
+    }))))
+}
+
+/// Store a new user defined function, written as (defun name (params...) body...).
+/// Functions are always defined in the global frame, but can see the variables around them.
+/// Returns the name of the function, as a symbol.
+fn defun(exp: &TokenExpression, env: &Environment) -> Result<Token, AtlasError> {
+    let name = name(exp.args.first(), "defun expects a function name")?;
+    let body = exp.args.get(2..).unwrap_or_default();
+    let function = procedure(Some(name.clone()), exp.args.get(1), body, env)?;
+    env.global().define(&name, function);
+    Ok(Token::Symbol(Symbol::new(&name)))
+}
+
+/// Create an anonymous function, written as (lambda (params...) body...).
+fn lambda(exp: &TokenExpression, env: &Environment) -> Result<Token, AtlasError> {
+    if exp.args.len() < 2 {
+        return Err(AtlasError::arity("lambda", "at least 2", exp.args.len()));
+    }
+    procedure(None, exp.args.first(), &exp.args[1..], env)
+}
+
+/// Bind a variable in the current frame, written as (define name value). Returns the name of
+/// the variable, as a symbol.
+fn define(exp: &TokenExpression, env: &Environment) -> Result<Token, AtlasError> {
+    let name = name(exp.args.first(), "define expects a variable name")?;
+    if exp.args.len() != 2 {
+        return Err(AtlasError::arity("define", "2", exp.args.len()));
+    }
+    let value = reduce_token(&exp.args[1], env)?;
+    env.define(&name, value);
+    Ok(Token::Symbol(Symbol::new(&name)))
+}
+
+/// Update an already bound variable, written as (set! name value). Returns the new value.
+fn set(exp: &TokenExpression, env: &Environment) -> Result<Token, AtlasError> {
+    let name = name(exp.args.first(), "set! expects a variable name")?;
+    if exp.args.len() != 2 {
+        return Err(AtlasError::arity("set!", "2", exp.args.len()));
+    }
+    let value = reduce_token(&exp.args[1], env)?;
+    env.set(&name, value.clone())?;
+    Ok(value)
+}
+
+/// Get the (name value) pairs of a let binding list.
+fn bindings(exp: &TokenExpression) -> Result<Vec<(String, &Token)>, AtlasError> {
+    expression(exp.args.first(), "let expects a binding list")?
+        .items()
+        .map(|binding| {
+            let binding = expression(Some(binding), "let bindings must be (name value)")?;
+            match (&binding.keyword, binding.args.as_slice()) {
+                (Some(Token::Variable(name)), [value]) => Ok((name.clone(), value)),
+                _ => Err(AtlasError::parse("let bindings must be (name value)")),
+            }
+        })
+        .collect()
+}
+
+/// Bind local variables for the body, written as (let ((name value)...) body...).
+/// Every value is reduced in the outer environment, so bindings can't see each other.
+fn let_(exp: &TokenExpression, env: &Environment) -> Result<Step, AtlasError> {
+    let frame = env.extend();
+    for (name, value) in bindings(exp)? {
+        frame.define(&name, reduce_token(value, env)?);
+    }
+    reduce_body(&exp.args[1..], &frame)
+}
+
+/// Like let, but every value can see the bindings that come before it.
+fn let_star(exp: &TokenExpression, env: &Environment) -> Result<Step, AtlasError> {
+    let mut frame = env.clone();
+    for (name, value) in bindings(exp)? {
+        let value = reduce_token(value, &frame)?;
+        frame = frame.extend();
+        frame.define(&name, value);
+    }
+    reduce_body(&exp.args[1..], &frame.extend())
+}
+
+/// Like let, but every value is reduced in the new frame, so bindings can refer to each other.
+fn letrec(exp: &TokenExpression, env: &Environment) -> Result<Step, AtlasError> {
+    let frame = env.extend();
+    for (name, value) in bindings(exp)? {
+        let value = reduce_token(value, &frame)?;
+        frame.define(&name, value);
+    }
+    reduce_body(&exp.args[1..], &frame)
+}
+
+/// Reduce only one of the branches, written as (if condition then else).
+/// Without an else branch, a false condition reduces to nil.
+fn if_(exp: &TokenExpression, env: &Environment) -> Result<Step, AtlasError> {
+    match exp.args.as_slice() {
+        [condition, then, rest @ ..] if rest.len() <= 1 => {
+            if reduce_token(condition, env)?.is_truthy() {
+                Ok(Step::Reduce(then.clone(), env.clone()))
+            } else if let Some(otherwise) = rest.first() {
+                Ok(Step::Reduce(otherwise.clone(), env.clone()))
+            } else {
+                Ok(Step::Done(Token::Nil))
+            }
+        }
+        _ => Err(AtlasError::arity("if", "2 or 3", exp.args.len())),
+    }
+}
+
+/// Reduce the body of the first clause whose test is true, written as
+/// (cond (test body...)... (else body...)). A clause without a body reduces to its test value.
+fn cond(exp: &TokenExpression, env: &Environment) -> Result<Step, AtlasError> {
+    for clause in &exp.args {
+        let clause = expression(Some(clause), "cond clauses must be (test body...)")?;
+        let test = match &clause.keyword {
+            Some(Token::Variable(name)) if name == "else" => Token::Boolean(true),
+            Some(test) => reduce_token(test, env)?,
+            None => return Err(AtlasError::parse("cond clauses must be (test body...)")),
+        };
+        if test.is_truthy() {
+            if clause.args.is_empty() {
+                return Ok(Step::Done(test));
+            }
+            return reduce_body(&clause.args, env);
+        }
+    }
+    Ok(Step::Done(Token::Nil))
+}
+
+/// Reduce the body only if the condition is the expected one, written as (when condition body...)
+/// or (unless condition body...). Otherwise reduces to nil.
+fn when(exp: &TokenExpression, env: &Environment, expected: bool) -> Result<Step, AtlasError> {
+    let condition = match exp.args.first() {
+        Some(condition) => condition,
+        None => {
+            return Err(AtlasError::arity(
+                if expected { "when" } else { "unless" },
+                "at least 1",
+                0,
+            ))
+        }
+    };
+    if reduce_token(condition, env)?.is_truthy() == expected && exp.args.len() > 1 {
+        reduce_body(&exp.args[1..], env)
+    } else {
+        Ok(Step::Done(Token::Nil))
+    }
+}
+
+/// Reduce the arguments in order until one is false, which is returned.
+/// Otherwise returns the last value, or true if there are no arguments.
+fn and(exp: &TokenExpression, env: &Environment) -> Result<Step, AtlasError> {
+    match exp.args.as_slice() {
+        [] => Ok(Step::Done(Token::Boolean(true))),
+        [rest @ .., last] => {
+            for arg in rest {
+                let result = reduce_token(arg, env)?;
+                if !result.is_truthy() {
+                    return Ok(Step::Done(result));
+                }
+            }
+            Ok(Step::Reduce(last.clone(), env.clone()))
+        }
+    }
+}
+
+/// Reduce the arguments in order until one is true, which is returned.
+/// Otherwise returns the last value, or false if there are no arguments.
+fn or(exp: &TokenExpression, env: &Environment) -> Result<Step, AtlasError> {
+    match exp.args.as_slice() {
+        [] => Ok(Step::Done(Token::Boolean(false))),
+        [rest @ .., last] => {
+            for arg in rest {
+                let result = reduce_token(arg, env)?;
+                if result.is_truthy() {
+                    return Ok(Step::Done(result));
+                }
+            }
+            Ok(Step::Reduce(last.clone(), env.clone()))
+        }
+    }
+}
+
+/// Split the items of a quoted expression from the tail written after a dot, as in (a b . c).
+/// The reader already rejected dots anywhere else than right before the last item.
+fn dotted(exp: &TokenExpression) -> (Vec<&Token>, Option<&Token>) {
+    let mut items: Vec<&Token> = exp.items().collect();
+    match items.as_slice() {
+        [.., Token::Variable(dot), tail] if dot == "." && items.len() > 2 => {
+            let tail = *tail;
+            items.truncate(items.len() - 2);
+            (items, Some(tail))
+        }
+        _ => (items, None),
+    }
+}
+
+/// Get the value of some code as data, written as (quote datum) or 'datum.
+/// Expressions become lists, names become symbols, and everything else is kept as written.
+fn datum(token: &Token) -> Token {
+    match token {
+        Token::Expression(exp) => {
+            let (items, tail) = dotted(exp);
+            Token::list_with_tail(items.into_iter().map(datum), tail.map_or(Token::Nil, datum))
+        }
+        Token::Variable(name) => Token::Symbol(Symbol::new(name)),
+        _ => token.clone(),
+    }
+}
+
+// get the only argument of an unquote or a nested quasiquote inside a template, if the
+// expression is one of them.
+fn template_form(exp: &TokenExpression) -> Option<(SpecialForms, &Token)> {
+    let form = SpecialForms::try_from(exp.keyword.as_ref()?).ok()?;
+    match (form, exp.args.as_slice()) {
+        (
+            form @ (SpecialForms::Quasiquote
+            | SpecialForms::Unquote
+            | SpecialForms::UnquoteSplicing),
+            [arg],
+        ) => Some((form, arg)),
+        _ => None,
+    }
+}
+
+/// Quote a template, reducing only the parts written as (unquote x) or ,x. Parts written as
+/// (unquote-splicing x) or ,@x must reduce to lists, whose items are inserted in place.
+/// Nested quasiquotes are kept as written, along with the unquotes inside them.
+fn quasiquote(template: &Token, env: &Environment, level: usize) -> Result<Token, AtlasError> {
+    let exp = match template {
+        Token::Expression(exp) => exp,
+        _ => return Ok(datum(template)),
+    };
+    // templates recurse through their parts, and through any unquote back into reduce_token
+    let _guard = env.enter()?;
+    match template_form(exp) {
+        Some((SpecialForms::Unquote, arg)) if level == 0 => return reduce_token(arg, env),
+        Some((SpecialForms::UnquoteSplicing, _)) if level == 0 => {
+            return Err(AtlasError::parse(
+                "unquote-splicing must be used inside a list",
+            ))
+        }
+        Some((form, arg)) => {
+            let level = match form {
+                SpecialForms::Quasiquote => level + 1,
+                _ => level - 1,
+            };
+            let keyword = exp.keyword.as_ref().map_or(Token::Nil, datum);
+            return Ok(Token::list([keyword, quasiquote(arg, env, level)?]));
+        }
+        None => (),
+    }
+    let (items, tail) = dotted(exp);
+    let mut list = Vec::with_capacity(items.len());
+    for item in items {
+        if let (Token::Expression(item), 0) = (item, level) {
+            if let Some((SpecialForms::UnquoteSplicing, arg)) = template_form(item) {
+                let spliced = reduce_token(arg, env)?;
+                match spliced.to_vec() {
+                    Some(spliced) => list.extend(spliced),
+                    None => return Err(AtlasError::type_error("a list", &spliced)),
+                }
+                continue;
+            }
+        }
+        list.push(quasiquote(item, env, level)?);
+    }
+    let tail = match tail {
+        Some(tail) => quasiquote(tail, env, level)?,
+        None => Token::Nil,
+    };
+    Ok(Token::list_with_tail(list, tail))
+}
@@ -0,0 +1,111 @@
+//! This module holds the functions building, taking apart and inspecting pairs and lists.
+
+use crate::{
+    error::AtlasError,
+    tree::{number::Number, Token},
+};
+
+use super::single;
+
+/// Hold the builtin list functions. They are all registered at once, as BuiltinFuncs::List.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ListFuncs {
+    Cons,
+    Car,
+    Cdr,
+    List,
+    IsNull,
+    IsPair,
+    IsList,
+    Length,
+    Append,
+    Reverse,
+}
+
+/// Get the items of an argument, failing if it is not a proper list. Positions count from 1.
+fn items(function: &str, position: usize, arg: &Token) -> Result<Vec<Token>, AtlasError> {
+    arg.to_vec()
+        .ok_or_else(|| AtlasError::type_error("a list", arg).for_argument(function, position))
+}
+
+impl ListFuncs {
+    /// Get the name this function is called by.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ListFuncs::Cons => "cons",
+            ListFuncs::Car => "car",
+            ListFuncs::Cdr => "cdr",
+            ListFuncs::List => "list",
+            ListFuncs::IsNull => "null?",
+            ListFuncs::IsPair => "pair?",
+            ListFuncs::IsList => "list?",
+            ListFuncs::Length => "length",
+            ListFuncs::Append => "append",
+            ListFuncs::Reverse => "reverse",
+        }
+    }
+    // reduce the already reduced arguments to a Token result.
+    pub fn exec(&self, args: &[Token]) -> Result<Token, AtlasError> {
+        let name = self.name();
+        match self {
+            ListFuncs::Cons => match args {
+                [car, cdr] => Ok(Token::cons(car.clone(), cdr.clone())),
+                _ => Err(AtlasError::arity(name, "2", args.len())),
+            },
+            ListFuncs::Car | ListFuncs::Cdr => match single(name, args)? {
+                Token::Pair(pair) if *self == ListFuncs::Car => Ok(pair.car.clone()),
+                Token::Pair(pair) => Ok(pair.cdr.clone()),
+                other => Err(AtlasError::type_error("a pair", other).for_argument(name, 1)),
+            },
+            ListFuncs::List => Ok(Token::list(args.iter().cloned())),
+            // nil is the empty list
+            ListFuncs::IsNull => Ok(Token::Boolean(matches!(single(name, args)?, Token::Nil))),
+            ListFuncs::IsPair => Ok(Token::Boolean(matches!(
+                single(name, args)?,
+                Token::Pair(_)
+            ))),
+            ListFuncs::IsList => Ok(Token::Boolean(single(name, args)?.is_list())),
+            ListFuncs::Length => {
+                let length = items(name, 1, single(name, args)?)?.len();
+                Ok(Token::Number(Number::from(length as i64)))
+            }
+            // every argument but the last is copied, the last one is shared as the tail
+            ListFuncs::Append => match args {
+                [] => Ok(Token::Nil),
+                [lists @ .., tail] => {
+                    let mut all = Vec::new();
+                    for (position, list) in (1..).zip(lists) {
+                        all.extend(items(name, position, list)?);
+                    }
+                    Ok(Token::list_with_tail(all, tail.clone()))
+                }
+            },
+            ListFuncs::Reverse => {
+                let items = items(name, 1, single(name, args)?)?;
+                Ok(Token::list(items.into_iter().rev()))
+            }
+        }
+    }
+}
+
+impl TryFrom<&Token> for ListFuncs {
+    type Error = &'static str;
+    fn try_from(value: &Token) -> Result<Self, Self::Error> {
+        match value {
+            Token::Variable(name) => match name.to_lowercase().trim() {
+                "cons" => Ok(ListFuncs::Cons),
+                "car" => Ok(ListFuncs::Car),
+                "cdr" => Ok(ListFuncs::Cdr),
+                "list" => Ok(ListFuncs::List),
+                "null?" => Ok(ListFuncs::IsNull),
+                "pair?" => Ok(ListFuncs::IsPair),
+                "list?" => Ok(ListFuncs::IsList),
+                "length" => Ok(ListFuncs::Length),
+                "append" => Ok(ListFuncs::Append),
+                "reverse" => Ok(ListFuncs::Reverse),
+                _ => Err("Unknown list function passed."),
+            },
+            _ => Err("Unknown list function passed."),
+        }
+    }
+}
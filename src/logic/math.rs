@@ -0,0 +1,298 @@
+//! This module holds the math library: integer division, powers, roots, trigonometry, rounding
+//! and number predicates.
+
+use std::cmp::Ordering;
+
+use num_bigint::BigInt;
+use num_integer::Integer;
+
+use crate::{
+    error::AtlasError,
+    tree::{number::Number, Token},
+};
+
+use super::{number, single};
+
+/// Hold the builtin math functions. They are all registered at once, as BuiltinFuncs::Math.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MathFuncs {
+    Quotient,
+    Remainder,
+    Modulo,
+    Abs,
+    Min,
+    Max,
+    Expt,
+    Sqrt,
+    Exp,
+    Log,
+    Sin,
+    Cos,
+    Tan,
+    Asin,
+    Acos,
+    Atan,
+    Floor,
+    Ceiling,
+    Round,
+    Truncate,
+    IsZero,
+    IsPositive,
+    IsNegative,
+    IsEven,
+    IsOdd,
+    IsNumber,
+    IsInteger,
+    IsRational,
+    IsExact,
+    IsInexact,
+}
+
+/// Get the integer an argument holds, exact or not, failing if it is not one.
+fn integer(function: &str, position: usize, arg: &Token) -> Result<Number, AtlasError> {
+    match arg {
+        Token::Number(number) if number.is_integer() => Ok(number.clone()),
+        _ => Err(AtlasError::type_error("an integer", arg).for_argument(function, position)),
+    }
+}
+
+/// Divide two integers, keeping the part of the result given by the functions. Exact integers
+/// give an exact result, and the result is inexact if any of them is.
+fn integer_division(
+    function: &str,
+    args: &[Token],
+    exact: fn(&BigInt, &BigInt) -> BigInt,
+    inexact: fn(f64, f64) -> f64,
+) -> Result<Token, AtlasError> {
+    let (dividend, divisor) = match args {
+        [a, b] => (integer(function, 1, a)?, integer(function, 2, b)?),
+        _ => return Err(AtlasError::arity(function, "2", args.len())),
+    };
+    // unlike with /, an inexact zero has no result either
+    if divisor.is_zero() {
+        return Err(AtlasError::runtime("division by zero"));
+    }
+    let result = match (&dividend, &divisor) {
+        (Number::Integer(a), Number::Integer(b)) => Number::Integer(exact(a, b)),
+        _ => Number::Float(inexact(dividend.to_f64(), divisor.to_f64())),
+    };
+    Ok(Token::Number(result))
+}
+
+/// Keep the argument that compares as expected against all the others, as in (max 1 2 3).
+/// The result is inexact if any argument is, and NaN wins over any number.
+fn extremum(function: &str, args: &[Token], expected: Ordering) -> Result<Token, AtlasError> {
+    if args.is_empty() {
+        return Err(AtlasError::arity(function, "at least 1", 0));
+    }
+    let mut result = number(function, 1, &args[0])?;
+    let mut exact = result.is_exact();
+    for (position, arg) in (2..).zip(&args[1..]) {
+        let arg = number(function, position, arg)?;
+        exact &= arg.is_exact();
+        match arg.compare(&result) {
+            Some(ordering) if ordering != expected => (),
+            // NaN is not ordered, so once found it stays
+            None if result.compare(&result).is_none() => (),
+            _ => result = arg,
+        }
+    }
+    if !exact {
+        result = Number::Float(result.to_f64());
+    }
+    Ok(Token::Number(result))
+}
+
+/// Apply a float function to the only argument. The result is always inexact.
+fn inexact(function: &str, args: &[Token], op: fn(f64) -> f64) -> Result<Token, AtlasError> {
+    let arg = number(function, 1, single(function, args)?)?;
+    Ok(Token::Number(Number::Float(op(arg.to_f64()))))
+}
+
+/// Check a property of the only argument, which must be a number.
+fn predicate(
+    function: &str,
+    args: &[Token],
+    check: fn(&Number) -> bool,
+) -> Result<Token, AtlasError> {
+    let arg = number(function, 1, single(function, args)?)?;
+    Ok(Token::Boolean(check(&arg)))
+}
+
+// checks if an integer, exact or not, is even.
+fn is_even(number: &Number) -> bool {
+    match number {
+        Number::Integer(integer) => integer.is_even(),
+        _ => number.to_f64() % 2.0 == 0.0,
+    }
+}
+
+impl MathFuncs {
+    /// Get the name this function is called by.
+    pub fn name(&self) -> &'static str {
+        match self {
+            MathFuncs::Quotient => "quotient",
+            MathFuncs::Remainder => "remainder",
+            MathFuncs::Modulo => "modulo",
+            MathFuncs::Abs => "abs",
+            MathFuncs::Min => "min",
+            MathFuncs::Max => "max",
+            MathFuncs::Expt => "expt",
+            MathFuncs::Sqrt => "sqrt",
+            MathFuncs::Exp => "exp",
+            MathFuncs::Log => "log",
+            MathFuncs::Sin => "sin",
+            MathFuncs::Cos => "cos",
+            MathFuncs::Tan => "tan",
+            MathFuncs::Asin => "asin",
+            MathFuncs::Acos => "acos",
+            MathFuncs::Atan => "atan",
+            MathFuncs::Floor => "floor",
+            MathFuncs::Ceiling => "ceiling",
+            MathFuncs::Round => "round",
+            MathFuncs::Truncate => "truncate",
+            MathFuncs::IsZero => "zero?",
+            MathFuncs::IsPositive => "positive?",
+            MathFuncs::IsNegative => "negative?",
+            MathFuncs::IsEven => "even?",
+            MathFuncs::IsOdd => "odd?",
+            MathFuncs::IsNumber => "number?",
+            MathFuncs::IsInteger => "integer?",
+            MathFuncs::IsRational => "rational?",
+            MathFuncs::IsExact => "exact?",
+            MathFuncs::IsInexact => "inexact?",
+        }
+    }
+    // reduce the already reduced arguments to a Token result.
+    pub fn exec(&self, args: &[Token]) -> Result<Token, AtlasError> {
+        let name = self.name();
+        match self {
+            // quotient and remainder round towards zero, modulo takes the sign of the divisor
+            MathFuncs::Quotient => {
+                integer_division(name, args, |a, b| a / b, |a, b| (a / b).trunc())
+            }
+            MathFuncs::Remainder => integer_division(name, args, |a, b| a % b, |a, b| a % b),
+            MathFuncs::Modulo => integer_division(name, args, BigInt::mod_floor, |a, b| {
+                a - b * (a / b).floor()
+            }),
+            MathFuncs::Abs => Ok(Token::Number(number(name, 1, single(name, args)?)?.abs())),
+            MathFuncs::Min => extremum(name, args, Ordering::Less),
+            MathFuncs::Max => extremum(name, args, Ordering::Greater),
+            MathFuncs::Expt => match args {
+                [base, exponent] => number(name, 1, base)?
+                    .pow(&number(name, 2, exponent)?)
+                    .map(Token::Number)
+                    .ok_or_else(|| AtlasError::runtime("division by zero")),
+                _ => Err(AtlasError::arity(name, "2", args.len())),
+            },
+            MathFuncs::Sqrt => Ok(Token::Number(number(name, 1, single(name, args)?)?.sqrt())),
+            MathFuncs::Exp => inexact(name, args, f64::exp),
+            // with a second argument, (log x b) is the logarithm of x in base b
+            MathFuncs::Log => match args {
+                [_] => inexact(name, args, f64::ln),
+                [x, base] => {
+                    let x = number(name, 1, x)?.to_f64();
+                    let base = number(name, 2, base)?.to_f64();
+                    Ok(Token::Number(Number::Float(x.ln() / base.ln())))
+                }
+                _ => Err(AtlasError::arity(name, "1 or 2", args.len())),
+            },
+            MathFuncs::Sin => inexact(name, args, f64::sin),
+            MathFuncs::Cos => inexact(name, args, f64::cos),
+            MathFuncs::Tan => inexact(name, args, f64::tan),
+            MathFuncs::Asin => inexact(name, args, f64::asin),
+            MathFuncs::Acos => inexact(name, args, f64::acos),
+            // with a second argument, (atan y x) is the angle of the point (x, y)
+            MathFuncs::Atan => match args {
+                [_] => inexact(name, args, f64::atan),
+                [y, x] => {
+                    let y = number(name, 1, y)?.to_f64();
+                    let x = number(name, 2, x)?.to_f64();
+                    Ok(Token::Number(Number::Float(y.atan2(x))))
+                }
+                _ => Err(AtlasError::arity(name, "1 or 2", args.len())),
+            },
+            MathFuncs::Floor => Ok(Token::Number(number(name, 1, single(name, args)?)?.floor())),
+            MathFuncs::Ceiling => Ok(Token::Number(number(name, 1, single(name, args)?)?.ceil())),
+            MathFuncs::Round => Ok(Token::Number(number(name, 1, single(name, args)?)?.round())),
+            MathFuncs::Truncate => Ok(Token::Number(
+                number(name, 1, single(name, args)?)?.truncate(),
+            )),
+            MathFuncs::IsZero => predicate(name, args, Number::is_zero),
+            MathFuncs::IsPositive => predicate(name, args, |number| {
+                number.compare(&Number::zero()) == Some(Ordering::Greater)
+            }),
+            MathFuncs::IsNegative => predicate(name, args, |number| {
+                number.compare(&Number::zero()) == Some(Ordering::Less)
+            }),
+            MathFuncs::IsEven => Ok(Token::Boolean(is_even(&integer(
+                name,
+                1,
+                single(name, args)?,
+            )?))),
+            MathFuncs::IsOdd => Ok(Token::Boolean(!is_even(&integer(
+                name,
+                1,
+                single(name, args)?,
+            )?))),
+            // the type predicates accept anything, answering false for what is not a number
+            MathFuncs::IsNumber => Ok(Token::Boolean(matches!(
+                single(name, args)?,
+                Token::Number(_)
+            ))),
+            MathFuncs::IsInteger => Ok(Token::Boolean(matches!(
+                single(name, args)?,
+                Token::Number(number) if number.is_integer()
+            ))),
+            // every number but infinities and NaN can be written as a fraction
+            MathFuncs::IsRational => Ok(Token::Boolean(matches!(
+                single(name, args)?,
+                Token::Number(number) if number.to_f64().is_finite() || number.is_exact()
+            ))),
+            MathFuncs::IsExact => predicate(name, args, Number::is_exact),
+            MathFuncs::IsInexact => predicate(name, args, |number| !number.is_exact()),
+        }
+    }
+}
+
+impl TryFrom<&Token> for MathFuncs {
+    type Error = &'static str;
+    fn try_from(value: &Token) -> Result<Self, Self::Error> {
+        match value {
+            Token::Variable(name) => match name.to_lowercase().trim() {
+                "quotient" => Ok(MathFuncs::Quotient),
+                "remainder" | "rem" => Ok(MathFuncs::Remainder),
+                "modulo" | "mod" => Ok(MathFuncs::Modulo),
+                "abs" => Ok(MathFuncs::Abs),
+                "min" => Ok(MathFuncs::Min),
+                "max" => Ok(MathFuncs::Max),
+                "expt" => Ok(MathFuncs::Expt),
+                "sqrt" => Ok(MathFuncs::Sqrt),
+                "exp" => Ok(MathFuncs::Exp),
+                "log" => Ok(MathFuncs::Log),
+                "sin" => Ok(MathFuncs::Sin),
+                "cos" => Ok(MathFuncs::Cos),
+                "tan" => Ok(MathFuncs::Tan),
+                "asin" => Ok(MathFuncs::Asin),
+                "acos" => Ok(MathFuncs::Acos),
+                "atan" => Ok(MathFuncs::Atan),
+                "floor" => Ok(MathFuncs::Floor),
+                "ceiling" => Ok(MathFuncs::Ceiling),
+                "round" => Ok(MathFuncs::Round),
+                "truncate" => Ok(MathFuncs::Truncate),
+                "zero?" => Ok(MathFuncs::IsZero),
+                "positive?" => Ok(MathFuncs::IsPositive),
+                "negative?" => Ok(MathFuncs::IsNegative),
+                "even?" => Ok(MathFuncs::IsEven),
+                "odd?" => Ok(MathFuncs::IsOdd),
+                "number?" => Ok(MathFuncs::IsNumber),
+                "integer?" => Ok(MathFuncs::IsInteger),
+                "rational?" => Ok(MathFuncs::IsRational),
+                "exact?" => Ok(MathFuncs::IsExact),
+                "inexact?" => Ok(MathFuncs::IsInexact),
+                _ => Err("Unknown math function passed."),
+            },
+            _ => Err("Unknown math function passed."),
+        }
+    }
+}
@@ -1,94 +1,407 @@
 //! This module holds all the language logic.
 
-use crate::tree::{Token, TokenExpression};
+use std::{cmp::Ordering, rc::Rc};
+
+use crate::{
+    error::AtlasError,
+    tree::{number::Number, symbol::Symbol, Token, TokenExpression},
+};
+
+use self::{
+    env::{Environment, Function},
+    forms::SpecialForms,
+    lists::ListFuncs,
+    math::MathFuncs,
+};
+
+/// This module holds the environments where variables and user defined functions live.
+pub mod env;
+/// This module holds the special forms, which reduce their arguments themselves.
+pub mod forms;
+/// This module holds the list functions, registered as a single group of builtins.
+pub mod lists;
+/// This module holds the math library, registered as a single group of builtins.
+pub mod math;
 
 /// Hold the language built in functions.
+#[derive(Debug, Clone, PartialEq)]
 pub enum BuiltinFuncs {
     Print,
+    Display,
+    Newline,
+    Not,
     Add,
     Substract,
     Multiply,
     Divide,
+    NumEqual,
+    Less,
+    Greater,
+    LessEqual,
+    GreaterEqual,
+    Eq,
+    Equal,
+    IsSymbol,
+    SymbolToString,
+    StringToSymbol,
+    // every function of the math library
+    Math(MathFuncs),
+    // every function working with pairs and lists
+    List(ListFuncs),
 }
 
-impl BuiltinFuncs {
-    // reduce the token expression to a Token result.
-    pub fn exec(exp: &TokenExpression) -> Result<Token, &'static str> {
-        let keyword = match &exp.keyword {
-            Some(kw) => kw,
-            None => return Err("Could not get keyword"),
+/// A procedure, which can be placed as the keyword of an expression or passed around as a value.
+#[derive(Debug, Clone)]
+pub enum Callable {
+    Builtin(BuiltinFuncs),
+    User(Rc<Function>),
+}
+
+impl Callable {
+    /// Get the name this procedure is called by in error messages.
+    pub fn name(&self) -> &str {
+        match self {
+            Callable::Builtin(builtin) => builtin.name(),
+            Callable::User(function) => function.name(),
+        }
+    }
+}
+
+/// User defined functions are only equal to themselves, even if another one has the same code.
+impl PartialEq for Callable {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Callable::Builtin(a), Callable::Builtin(b)) => a == b,
+            (Callable::User(a), Callable::User(b)) => Rc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+/// Reduce a single token to its value. Nested expressions are reduced, and variables are
+/// resolved in the given environment. Names not bound there refer to the builtin functions, so
+/// user definitions shadow them.
+pub fn reduce_token(token: &Token, env: &Environment) -> Result<Token, AtlasError> {
+    match token {
+        Token::Expression(exp) => reduce_expression(exp, env),
+        Token::Variable(name) => match env.get(name) {
+            Some(value) => Ok(value),
+            None => match BuiltinFuncs::try_from(token) {
+                Ok(builtin) => Ok(Token::Procedure(Callable::Builtin(builtin))),
+                Err(_) => Err(AtlasError::unbound(name)),
+            },
+        },
+        _ => Ok(token.clone()),
+    }
+}
+
+/// What is left to do after a step of reducing an expression.
+pub enum Step {
+    // The expression was reduced to this value
+    Done(Token),
+    // The value of the expression is the value of this token, in this environment. Tokens in
+    // tail position are left to the caller, so calls in them don't grow the stack.
+    Reduce(Token, Environment),
+}
+
+/// Reduce an expression to a Token result. Special forms get their arguments as written, while
+/// procedures get them already reduced, from left to right. Any keyword that is not a special
+/// form is reduced first, so it can be an expression returning a procedure.
+/// Expressions in tail position are reduced in a loop, so recursive functions calling themselves
+/// as their last step run in constant stack space.
+/// Errors without a span get the span of the innermost expression they happened in.
+pub fn reduce_expression(exp: &TokenExpression, env: &Environment) -> Result<Token, AtlasError> {
+    // only nested expressions count towards the depth limit, tail calls don't
+    let _guard = env.enter().map_err(|err| in_expression(err, exp))?;
+    let mut step = apply_spanned(exp, env)?;
+    loop {
+        match step {
+            Step::Done(value) => return Ok(value),
+            Step::Reduce(Token::Expression(exp), env) => step = apply_spanned(&exp, &env)?,
+            Step::Reduce(token, env) => return reduce_token(&token, &env),
+        }
+    }
+}
+
+// give an error without a span the span of the expression it happened in.
+fn in_expression(err: AtlasError, exp: &TokenExpression) -> AtlasError {
+    match exp.span() {
+        Some(span) => err.with_span(span),
+        None => err,
+    }
+}
+
+// apply an expression, giving errors without a span the span of this expression.
+fn apply_spanned(exp: &TokenExpression, env: &Environment) -> Result<Step, AtlasError> {
+    apply(exp, env).map_err(|err| in_expression(err, exp))
+}
+
+fn apply(exp: &TokenExpression, env: &Environment) -> Result<Step, AtlasError> {
+    let keyword = match &exp.keyword {
+        Some(kw) => kw,
+        None => return Err(AtlasError::runtime("Can't reduce an empty expression")),
+    };
+    // special forms must not reduce their arguments, they decide what to reduce and when
+    if let Ok(form) = SpecialForms::try_from(keyword) {
+        return form.exec(exp, env);
+    }
+    let callable = match reduce_token(keyword, env)? {
+        Token::Procedure(callable) => callable,
+        other => return Err(AtlasError::type_error("a procedure", &other)),
+    };
+    let mut args = Vec::with_capacity(exp.args.len());
+    for arg in &exp.args {
+        args.push(reduce_token(arg, env)?);
+    }
+    match callable {
+        Callable::Builtin(builtin) => Ok(Step::Done(builtin.exec(&args, env)?)),
+        Callable::User(function) => call(&function, args),
+    }
+}
+
+/// Call a user defined function, binding each argument to its parameter name in a new frame.
+/// The frame is nested in the one the function was defined in, so the caller's variables are
+/// not visible. The last token of the body is left to reduce to the caller.
+fn call(function: &Function, args: Vec<Token>) -> Result<Step, AtlasError> {
+    if args.len() != function.params.len() {
+        return Err(AtlasError::arity(
+            function.name(),
+            function.params.len().to_string(),
+            args.len(),
+        ));
+    }
+    let frame = function.env.extend();
+    for (param, arg) in function.params.iter().zip(args) {
+        frame.define(param, arg);
+    }
+    reduce_body(&function.body, &frame)
+}
+
+/// Reduce a sequence of tokens in order. The last one is in tail position, so it is left to
+/// reduce to the caller.
+pub fn reduce_body(body: &[Token], env: &Environment) -> Result<Step, AtlasError> {
+    match body {
+        [] => Err(AtlasError::runtime("Tried to reduce an empty body")),
+        [rest @ .., last] => {
+            for exp in rest {
+                reduce_token(exp, env)?;
+            }
+            Ok(Step::Reduce(last.clone(), env.clone()))
+        }
+    }
+}
+
+/// Get the only argument of a function, failing if there are more or less.
+fn single<'a>(function: &str, args: &'a [Token]) -> Result<&'a Token, AtlasError> {
+    match args {
+        [arg] => Ok(arg),
+        _ => Err(AtlasError::arity(function, "1", args.len())),
+    }
+}
+
+/// Get the number an argument holds, failing if it is not one. Positions count from 1.
+fn number(function: &str, position: usize, arg: &Token) -> Result<Number, AtlasError> {
+    match arg {
+        Token::Number(number) => Ok(number.clone()),
+        _ => Err(AtlasError::type_error("a number", arg).for_argument(function, position)),
+    }
+}
+
+/// Combine numeric arguments from left to right, starting from the given value.
+fn fold(
+    function: &str,
+    args: &[Token],
+    initial: Number,
+    op: fn(Number, Number) -> Result<Number, AtlasError>,
+) -> Result<Token, AtlasError> {
+    fold_from(function, args, 1, initial, op)
+}
+
+/// Like fold, for arguments whose first one is at the given position.
+fn fold_from(
+    function: &str,
+    args: &[Token],
+    first_position: usize,
+    initial: Number,
+    op: fn(Number, Number) -> Result<Number, AtlasError>,
+) -> Result<Token, AtlasError> {
+    let mut result = initial;
+    for (position, arg) in (first_position..).zip(args) {
+        result = op(result, number(function, position, arg)?)?;
+    }
+    Ok(Token::Number(result))
+}
+
+/// Divide two numbers. Dividing an exact number by an exact zero has no result, while floats
+/// follow IEEE 754 and give an infinity or NaN.
+fn divide(dividend: Number, divisor: Number) -> Result<Number, AtlasError> {
+    dividend
+        .checked_div(&divisor)
+        .ok_or_else(|| AtlasError::runtime("division by zero"))
+}
+
+/// Check that every pair of consecutive arguments is ordered as expected, as in (< 1 2 3).
+/// Numbers are compared with numbers, and string literals with string literals.
+fn compare(
+    name: &'static str,
+    args: &[Token],
+    expected: fn(Ordering) -> bool,
+) -> Result<Token, AtlasError> {
+    match args {
+        [] => return Err(AtlasError::arity(name, "at least 1", 0)),
+        [Token::Number(_) | Token::Literal(_)] => (),
+        [other] => {
+            return Err(
+                AtlasError::type_error("a number or a string literal", other).for_argument(name, 1),
+            )
+        }
+        _ => (),
+    }
+    let mut ordered = true;
+    for (position, pair) in (1..).zip(args.windows(2)) {
+        let ordering = match (&pair[0], &pair[1]) {
+            (Token::Number(a), Token::Number(b)) => a.compare(b),
+            (Token::Literal(a), Token::Literal(b)) => Some(a.cmp(b)),
+            (Token::Number(_), other) => {
+                return Err(
+                    AtlasError::type_error("a number", other).for_argument(name, position + 1)
+                )
+            }
+            (Token::Literal(_), other) => {
+                return Err(AtlasError::type_error("a string literal", other)
+                    .for_argument(name, position + 1))
+            }
+            (other, _) => {
+                return Err(
+                    AtlasError::type_error("a number or a string literal", other)
+                        .for_argument(name, position),
+                )
+            }
         };
-        match BuiltinFuncs::try_from(keyword) {
-            Ok(BuiltinFuncs::Print) => Ok(Token::Literal(String::from("TODO"))),
-            Ok(BuiltinFuncs::Add) => {
-                let mut result: f64 = 0.0;
-                for arg in &exp.args {
-                    if let Token::Number(n) = arg {
-                        result += n;
-                    // TODO: we should get the expression result, but currently it's becoming increasingly
-                    // difficult due to ownership. I'll have to rethink this.
-                    } else {
-                        return Err("Some expression was not correctly reduced while performing an operation,
-                        or an illegal argument has been passed.");
-                    }
-                }
-                Ok(Token::Number(result))
+        // NaN is not ordered, so no comparison holds for it. The remaining arguments are still
+        // checked, so wrong types are reported whatever the order of the others.
+        ordered &= ordering.is_some_and(expected);
+    }
+    Ok(Token::Boolean(ordered))
+}
+
+impl BuiltinFuncs {
+    /// Get the name this function is called by.
+    pub fn name(&self) -> &'static str {
+        match self {
+            BuiltinFuncs::Print => "print",
+            BuiltinFuncs::Display => "display",
+            BuiltinFuncs::Newline => "newline",
+            BuiltinFuncs::Not => "not",
+            BuiltinFuncs::Add => "+",
+            BuiltinFuncs::Substract => "-",
+            BuiltinFuncs::Multiply => "*",
+            BuiltinFuncs::Divide => "/",
+            BuiltinFuncs::NumEqual => "=",
+            BuiltinFuncs::Less => "<",
+            BuiltinFuncs::Greater => ">",
+            BuiltinFuncs::LessEqual => "<=",
+            BuiltinFuncs::GreaterEqual => ">=",
+            BuiltinFuncs::Eq => "eq?",
+            BuiltinFuncs::Equal => "equal?",
+            BuiltinFuncs::IsSymbol => "symbol?",
+            BuiltinFuncs::SymbolToString => "symbol->string",
+            BuiltinFuncs::StringToSymbol => "string->symbol",
+            BuiltinFuncs::Math(math) => math.name(),
+            BuiltinFuncs::List(list) => list.name(),
+        }
+    }
+    // reduce the already reduced arguments to a Token result.
+    pub fn exec(&self, args: &[Token], env: &Environment) -> Result<Token, AtlasError> {
+        match self {
+            // print writes its arguments separated by spaces, ending the line
+            BuiltinFuncs::Print => {
+                let line: Vec<String> = args.iter().map(Token::to_display_string).collect();
+                let output = env.output();
+                output.write(&line.join(" "))?;
+                output.write("\n")?;
+                output.flush()?;
+                Ok(Token::Nil)
             }
-            Ok(BuiltinFuncs::Substract) => {
-                // use first argument as the initial value
-                let mut arg_iter = exp.args.iter();
-                let mut result: f64 = match exp.args[0] {
-                    Token::Number(num) => num,
-                    _ => 0.0,
-                };
-                arg_iter.next();
-                for arg in arg_iter {
-                    if let Token::Number(n) = arg {
-                        result -= n;
-                    // TODO: we should get the expression result, but currently it's becoming increasingly
-                    // difficult due to ownership. I'll have to rethink this.
-                    } else {
-                        return Err("Some expression was not correctly reduced while performing an operation,
-                        or an illegal argument has been passed.");
-                    }
+            // display writes its arguments as they are, without separators
+            BuiltinFuncs::Display => {
+                let output = env.output();
+                for arg in args {
+                    output.write(&arg.to_display_string())?;
                 }
-                Ok(Token::Number(result))
+                output.flush()?;
+                Ok(Token::Nil)
             }
-            Ok(BuiltinFuncs::Multiply) => {
-                let mut result: f64 = 1.0;
-                for arg in &exp.args {
-                    if let Token::Number(n) = arg {
-                        result *= n;
-                    // TODO: we should get the expression result, but currently it's becoming increasingly
-                    // difficult due to ownership. I'll have to rethink this.
-                    } else {
-                        return Err("Some expression was not correctly reduced while performing an operation,
-                        or an illegal argument has been passed.");
-                    }
+            BuiltinFuncs::Newline => match args {
+                [] => {
+                    let output = env.output();
+                    output.write("\n")?;
+                    output.flush()?;
+                    Ok(Token::Nil)
                 }
-                Ok(Token::Number(result))
-            }
-            Ok(BuiltinFuncs::Divide) => {
-                // use first argument as the initial value
-                let mut arg_iter = exp.args.iter();
-                let mut result: f64 = match exp.args[0] {
-                    Token::Number(num) => num,
-                    _ => 1.0,
-                };
-                arg_iter.next();
-                for arg in arg_iter {
-                    if let Token::Number(n) = arg {
-                        result /= n;
-                    // TODO: we should get the expression result, but currently it's becoming increasingly
-                    // difficult due to ownership. I'll have to rethink this.
-                    } else {
-                        return Err("Some expression was not correctly reduced while performing an operation,
-                        or an illegal argument has been passed.");
-                    }
+                _ => Err(AtlasError::arity("newline", "no", args.len())),
+            },
+            BuiltinFuncs::Not => match args {
+                [arg] => Ok(Token::Boolean(!arg.is_truthy())),
+                _ => Err(AtlasError::arity("not", "1", args.len())),
+            },
+            BuiltinFuncs::NumEqual => compare(self.name(), args, Ordering::is_eq),
+            BuiltinFuncs::Less => compare(self.name(), args, Ordering::is_lt),
+            BuiltinFuncs::Greater => compare(self.name(), args, Ordering::is_gt),
+            BuiltinFuncs::LessEqual => compare(self.name(), args, Ordering::is_le),
+            BuiltinFuncs::GreaterEqual => compare(self.name(), args, Ordering::is_ge),
+            // pairs are shared when passed around, so eq? only holds for the same pair. Other
+            // values are copied, so identity is the same as equality for them.
+            BuiltinFuncs::Eq => match args {
+                [Token::Pair(a), Token::Pair(b)] => Ok(Token::Boolean(Rc::ptr_eq(a, b))),
+                [a, b] => Ok(Token::Boolean(a == b)),
+                _ => Err(AtlasError::arity(self.name(), "2", args.len())),
+            },
+            // equal? compares lists item by item
+            BuiltinFuncs::Equal => match args {
+                [a, b] => Ok(Token::Boolean(a == b)),
+                _ => Err(AtlasError::arity(self.name(), "2", args.len())),
+            },
+            BuiltinFuncs::IsSymbol => Ok(Token::Boolean(matches!(
+                single(self.name(), args)?,
+                Token::Symbol(_)
+            ))),
+            BuiltinFuncs::SymbolToString => match single(self.name(), args)? {
+                Token::Symbol(symbol) => Ok(Token::Literal(symbol.name().to_string())),
+                other => {
+                    Err(AtlasError::type_error("a symbol", other).for_argument(self.name(), 1))
+                }
+            },
+            // any string can name a symbol, even if it could not be written as one
+            BuiltinFuncs::StringToSymbol => {
+                match single(self.name(), args)? {
+                    Token::Literal(string) => Ok(Token::Symbol(Symbol::new(string))),
+                    other => Err(AtlasError::type_error("a string literal", other)
+                        .for_argument(self.name(), 1)),
                 }
-                Ok(Token::Number(result))
             }
-            _ => Err("Unknown builtin function"),
+            BuiltinFuncs::Add => fold(self.name(), args, Number::zero(), |a, b| Ok(a + b)),
+            BuiltinFuncs::Multiply => fold(self.name(), args, Number::one(), |a, b| Ok(a * b)),
+            // with a single argument, (- x) is its negation
+            BuiltinFuncs::Substract => match args {
+                [] => Err(AtlasError::arity(self.name(), "at least 1", 0)),
+                [arg] => Ok(Token::Number(-number(self.name(), 1, arg)?)),
+                [first, rest @ ..] => {
+                    let first = number(self.name(), 1, first)?;
+                    fold_from(self.name(), rest, 2, first, |a, b| Ok(a - b))
+                }
+            },
+            // with a single argument, (/ x) is its reciprocal
+            BuiltinFuncs::Divide => match args {
+                [] => Err(AtlasError::arity(self.name(), "at least 1", 0)),
+                [_] => fold_from(self.name(), args, 1, Number::one(), divide),
+                [first, rest @ ..] => {
+                    let first = number(self.name(), 1, first)?;
+                    fold_from(self.name(), rest, 2, first, divide)
+                }
+            },
+            BuiltinFuncs::Math(math) => math.exec(args),
+            BuiltinFuncs::List(list) => list.exec(args),
         }
     }
 }
@@ -102,14 +415,30 @@ impl TryFrom<&Token> for BuiltinFuncs {
                 // match strings to known builtin functions
                 match name.to_lowercase().trim() {
                     "print" => Ok(BuiltinFuncs::Print),
+                    "display" => Ok(BuiltinFuncs::Display),
+                    "newline" => Ok(BuiltinFuncs::Newline),
+                    "not" => Ok(BuiltinFuncs::Not),
                     "+" => Ok(BuiltinFuncs::Add),
                     "-" => Ok(BuiltinFuncs::Substract),
                     "*" => Ok(BuiltinFuncs::Multiply),
                     "/" => Ok(BuiltinFuncs::Divide),
-                    _ => Err("Unknown function passed."),
+                    "=" => Ok(BuiltinFuncs::NumEqual),
+                    "<" => Ok(BuiltinFuncs::Less),
+                    ">" => Ok(BuiltinFuncs::Greater),
+                    "<=" => Ok(BuiltinFuncs::LessEqual),
+                    ">=" => Ok(BuiltinFuncs::GreaterEqual),
+                    "eq?" => Ok(BuiltinFuncs::Eq),
+                    "equal?" => Ok(BuiltinFuncs::Equal),
+                    "symbol?" => Ok(BuiltinFuncs::IsSymbol),
+                    "symbol->string" => Ok(BuiltinFuncs::SymbolToString),
+                    "string->symbol" => Ok(BuiltinFuncs::StringToSymbol),
+                    _ => MathFuncs::try_from(value)
+                        .map(BuiltinFuncs::Math)
+                        .or_else(|_| ListFuncs::try_from(value).map(BuiltinFuncs::List))
+                        .map_err(|_| "Unknown function passed."),
                 }
             }
-            // TODO: others, user defined functions and such
+            // user defined functions are looked up in the environment, see reduce_token
             _ => Err("Unknown function passed."),
         }
     }
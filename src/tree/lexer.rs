@@ -0,0 +1,267 @@
+//! This module holds the lexer, which splits the source code into lexemes.
+
+use std::{iter::Peekable, str::CharIndices};
+
+use crate::error::AtlasError;
+
+use super::Symbols;
+
+/// Byte offsets of the first character of something in the source, and one past its last one.
+pub type Span = (usize, usize);
+
+/// Checks if a character separates lexemes. Every Unicode whitespace character does, so source
+/// code can be formatted with any mix of spaces, tabs and line breaks. A byte order mark, which
+/// some editors put at the start of files, is treated as whitespace too.
+pub fn is_separator(char: char) -> bool {
+    char.is_whitespace() || char == '\u{feff}'
+}
+
+/// Checks if a character ends a line, and with it a line comment.
+pub fn is_line_terminator(char: char) -> bool {
+    matches!(
+        char,
+        '\n' | '\r' | '\u{b}' | '\u{c}' | '\u{85}' | '\u{2028}' | '\u{2029}'
+    )
+}
+
+/// The smallest meaningful pieces of the source code.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Lexeme {
+    LParen,
+    RParen,
+    // The contents of a string literal, between its quotes
+    Str(String),
+    // Anything else, such as a number or a name, as written in the source
+    Atom(String),
+    // A #; comment, which comments out the form that follows it
+    DatumComment,
+    // A ', `, , or ,@ before a form, holding the name of the special form it is short for
+    Abbreviation(&'static str),
+}
+
+/// A lexeme, together with where it was found in the source.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpannedLexeme {
+    pub lexeme: Lexeme,
+    pub span: Span,
+}
+
+/// Iterates over the lexemes of some source code.
+pub struct Lexer<'a> {
+    source: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+    // where the source starts in the code spans point into
+    offset: usize,
+}
+
+impl<'a> Lexer<'a> {
+    /// Create a new lexer for the given source code.
+    pub fn new(source: &'a str) -> Self {
+        Lexer::new_at(source, 0)
+    }
+    /// Create a new lexer for the source code starting at the given byte offset. Spans are
+    /// still offsets into the whole source.
+    pub fn new_at(source: &'a str, start: usize) -> Self {
+        let source = &source[start..];
+        Lexer {
+            source,
+            chars: source.char_indices().peekable(),
+            offset: start,
+        }
+    }
+}
+
+impl Lexer<'_> {
+    // lex the rest of a string literal whose opening quote has already been consumed, decoding
+    // its escape sequences.
+    fn string(&mut self, start: usize) -> Result<SpannedLexeme, AtlasError> {
+        let mut string = String::new();
+        while let Some((index, char)) = self.chars.next() {
+            match char {
+                '"' => {
+                    return Ok(SpannedLexeme {
+                        lexeme: Lexeme::Str(string),
+                        span: (start, index + 1),
+                    })
+                }
+                '\\' => match self.chars.next() {
+                    Some((_, escaped)) => string.push(self.escape(index, escaped)?),
+                    None => break,
+                },
+                _ => string.push(char),
+            }
+        }
+        Err(AtlasError::parse("unterminated string literal").with_span((start, start + 1)))
+    }
+
+    // decode the escape sequence starting at the given backslash, whose next character has
+    // already been consumed.
+    fn escape(&mut self, start: usize, escaped: char) -> Result<char, AtlasError> {
+        match escaped {
+            'n' => Ok('\n'),
+            't' => Ok('\t'),
+            'r' => Ok('\r'),
+            '0' => Ok('\0'),
+            '"' => Ok('"'),
+            '\\' => Ok('\\'),
+            'u' => self.unicode_escape(start),
+            _ => Err(
+                AtlasError::parse(format!("unknown character escape `\\{}`", escaped))
+                    .with_span((start, start + 1 + escaped.len_utf8())),
+            ),
+        }
+    }
+
+    // decode the rest of a \u{...} escape, holding up to six hex digits.
+    fn unicode_escape(&mut self, start: usize) -> Result<char, AtlasError> {
+        let mut digits = String::new();
+        let mut end = start + 2;
+        if self.chars.next_if(|(_, char)| *char == '{').is_some() {
+            end += 1;
+            while let Some((index, char)) = self.chars.next_if(|(_, char)| *char != '"') {
+                end = index + char.len_utf8();
+                if char == '}' {
+                    return u32::from_str_radix(&digits, 16)
+                        .ok()
+                        .filter(|_| digits.len() <= 6)
+                        .and_then(char::from_u32)
+                        .ok_or_else(|| {
+                            AtlasError::parse("invalid unicode character escape")
+                                .with_span((start, end))
+                        });
+                }
+                digits.push(char);
+            }
+        }
+        Err(
+            AtlasError::parse("unicode escapes must be written as \\u{...}")
+                .with_span((start, end)),
+        )
+    }
+
+    // skip a #| |# comment starting at the given position. Block comments can be nested.
+    fn block_comment(&mut self, start: usize) -> Result<(), AtlasError> {
+        // skip the opening #|
+        self.chars.nth(1);
+        let mut depth = 1;
+        while let Some((index, _)) = self.chars.next() {
+            let rest = &self.source[index..];
+            if rest.starts_with("|#") {
+                self.chars.next();
+                depth -= 1;
+                if depth == 0 {
+                    return Ok(());
+                }
+            } else if rest.starts_with("#|") {
+                self.chars.next();
+                depth += 1;
+            }
+        }
+        Err(AtlasError::parse("unterminated block comment").with_span((start, start + 2)))
+    }
+
+    // skip whitespace and comments until the next lexeme.
+    fn skip_separators(&mut self) -> Result<(), AtlasError> {
+        loop {
+            while self
+                .chars
+                .next_if(|(_, char)| is_separator(*char))
+                .is_some()
+            {}
+            let start = match self.chars.peek() {
+                Some((start, _)) => *start,
+                None => return Ok(()),
+            };
+            let rest = &self.source[start..];
+            if rest.starts_with(';') {
+                // line comments go on until the end of the line
+                while self
+                    .chars
+                    .next_if(|(_, char)| !is_line_terminator(*char))
+                    .is_some()
+                {}
+            } else if rest.starts_with("#|") {
+                self.block_comment(start)?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    // lex the rest of an atom, which goes on until a separator, a comment or a symbol,
+    // as in "(+ 1 2)"
+    fn atom(&mut self, start: usize) -> SpannedLexeme {
+        while self
+            .chars
+            .next_if(|(_, char)| {
+                !is_separator(*char) && *char != ';' && Symbols::try_from(*char).is_err()
+            })
+            .is_some()
+        {}
+        let end = self.chars.peek().map_or(self.source.len(), |(end, _)| *end);
+        SpannedLexeme {
+            lexeme: Lexeme::Atom(self.source[start..end].to_string()),
+            span: (start, end),
+        }
+    }
+}
+
+impl Iterator for Lexer<'_> {
+    type Item = Result<SpannedLexeme, AtlasError>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let offset = self.offset;
+        let lexeme = self.lex()?;
+        Some(
+            lexeme
+                .map(|spanned| SpannedLexeme {
+                    span: (spanned.span.0 + offset, spanned.span.1 + offset),
+                    ..spanned
+                })
+                .map_err(|err| err.offset_span(offset)),
+        )
+    }
+}
+
+impl Lexer<'_> {
+    // lex the next lexeme, with spans counted from the start of the lexed source.
+    fn lex(&mut self) -> Option<Result<SpannedLexeme, AtlasError>> {
+        // skip everything separating lexemes
+        if let Err(err) = self.skip_separators() {
+            return Some(Err(err));
+        }
+        let (start, char) = self.chars.next()?;
+        if char == '#' && self.chars.next_if(|(_, char)| *char == ';').is_some() {
+            return Some(Ok(SpannedLexeme {
+                lexeme: Lexeme::DatumComment,
+                span: (start, start + 2),
+            }));
+        }
+        // 'x is read as (quote x), and the same goes for the quasiquote abbreviations
+        let abbreviation = match char {
+            '\'' => Some("quote"),
+            '`' => Some("quasiquote"),
+            ',' if self.chars.next_if(|(_, char)| *char == '@').is_some() => {
+                Some("unquote-splicing")
+            }
+            ',' => Some("unquote"),
+            _ => None,
+        };
+        if let Some(name) = abbreviation {
+            let end = self.chars.peek().map_or(self.source.len(), |(end, _)| *end);
+            return Some(Ok(SpannedLexeme {
+                lexeme: Lexeme::Abbreviation(name),
+                span: (start, end),
+            }));
+        }
+        let lexeme = match Symbols::try_from(char) {
+            Ok(Symbols::LPAREN) => Lexeme::LParen,
+            Ok(Symbols::RPAREN) => Lexeme::RParen,
+            Ok(Symbols::QUOTE) => return Some(self.string(start)),
+            Err(_) => return Some(Ok(self.atom(start))),
+        };
+        Some(Ok(SpannedLexeme {
+            lexeme,
+            span: (start, start + char.len_utf8()),
+        }))
+    }
+}
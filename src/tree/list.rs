@@ -0,0 +1,183 @@
+//! This module holds cons cells, the pairs lists are built from.
+
+use std::{
+    fmt::{self, Debug, Display, Formatter},
+    mem,
+    rc::Rc,
+};
+
+use super::Token;
+
+/// A cons cell. A list is a chain of pairs, each holding an item and the rest of the list,
+/// ending with nil. Pairs can't be changed once built, so they are shared between lists.
+#[derive(Clone)]
+pub struct Pair {
+    // The first item
+    pub car: Token,
+    // The rest of the list, or any other value for improper lists such as (a . b)
+    pub cdr: Token,
+}
+
+impl Pair {
+    /// Create a new pair.
+    pub fn new(car: Token, cdr: Token) -> Self {
+        Pair { car, cdr }
+    }
+}
+
+/// Pairs are equal if they hold equal items. Lists are compared with a work stack instead of
+/// recursing, so lists nested through either side of their pairs can be as deep as memory allows.
+impl PartialEq for Pair {
+    fn eq(&self, other: &Self) -> bool {
+        let mut pending = vec![(self, other)];
+        while let Some((a, b)) = pending.pop() {
+            for (a, b) in [(&a.car, &b.car), (&a.cdr, &b.cdr)] {
+                match (a, b) {
+                    // shared pairs are equal without looking inside them
+                    (Token::Pair(a), Token::Pair(b)) if Rc::ptr_eq(a, b) => (),
+                    (Token::Pair(a), Token::Pair(b)) => pending.push((a, b)),
+                    _ if a != b => return false,
+                    _ => (),
+                }
+            }
+        }
+        true
+    }
+}
+
+// deep lists would otherwise be dropped recursively, one stack frame per pair, so the pairs
+// only used by this one are taken apart on a work stack instead.
+impl Drop for Pair {
+    fn drop(&mut self) {
+        let mut pending = Vec::new();
+        let take = |token: &mut Token, pending: &mut Vec<Rc<Pair>>| {
+            if let Token::Pair(pair) = mem::replace(token, Token::Nil) {
+                pending.push(pair);
+            }
+        };
+        take(&mut self.car, &mut pending);
+        take(&mut self.cdr, &mut pending);
+        while let Some(pair) = pending.pop() {
+            // pairs still used by another list are left to it
+            if let Ok(mut pair) = Rc::try_unwrap(pair) {
+                take(&mut pair.car, &mut pending);
+                take(&mut pair.cdr, &mut pending);
+            }
+        }
+    }
+}
+
+/// Iterates over the items of a list. Once done, the tail tells if the list was proper.
+pub struct ListIter<'a> {
+    rest: &'a Token,
+}
+
+impl<'a> ListIter<'a> {
+    /// Get what is left of the list. After iterating over all the items, it is nil for proper
+    /// lists, and the value after the dot for improper ones.
+    pub fn tail(&self) -> &'a Token {
+        self.rest
+    }
+}
+
+impl<'a> Iterator for ListIter<'a> {
+    type Item = &'a Token;
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.rest {
+            Token::Pair(pair) => {
+                self.rest = &pair.cdr;
+                Some(&pair.car)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Token {
+    /// Build a pair out of an item and the rest of a list.
+    pub fn cons(car: Token, cdr: Token) -> Token {
+        Token::Pair(Rc::new(Pair::new(car, cdr)))
+    }
+    /// Build a proper list holding the given items, in order. No items give nil.
+    pub fn list(items: impl IntoIterator<Item = Token, IntoIter: DoubleEndedIterator>) -> Token {
+        Token::list_with_tail(items, Token::Nil)
+    }
+    /// Build a list holding the given items, in order, followed by the given tail.
+    pub fn list_with_tail(
+        items: impl IntoIterator<Item = Token, IntoIter: DoubleEndedIterator>,
+        tail: Token,
+    ) -> Token {
+        items
+            .into_iter()
+            .rev()
+            .fold(tail, |rest, item| Token::cons(item, rest))
+    }
+    /// Iterate over the items of this token, if it is a list. Other values have no items.
+    pub fn iter_list(&self) -> ListIter<'_> {
+        ListIter { rest: self }
+    }
+    /// Get the items of this token if it is a proper list, one ending with nil.
+    pub fn to_vec(&self) -> Option<Vec<Token>> {
+        let mut items = self.iter_list();
+        let vec = items.by_ref().cloned().collect();
+        match items.tail() {
+            Token::Nil => Some(vec),
+            _ => None,
+        }
+    }
+    /// Checks if this token is a proper list, one ending with nil. Nil is the empty list.
+    pub fn is_list(&self) -> bool {
+        let mut items = self.iter_list();
+        items.by_ref().for_each(drop);
+        matches!(items.tail(), Token::Nil)
+    }
+}
+
+// what is left to print of a list, see Display for Pair.
+enum Printing<'a> {
+    // A whole value, which is a list of its own if it is a pair
+    Value(&'a Token),
+    // The rest of a list, after at least one of its items
+    Rest(&'a Token),
+    Text(&'static str),
+}
+
+/// Print lists as (1 2 3), and improper ones with a dot before their tail, as (1 2 . 3).
+/// Lists are printed with a work stack instead of recursing, so they can be nested as deep as
+/// memory allows.
+impl Display for Pair {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "(")?;
+        let mut pending = vec![Printing::Rest(&self.cdr), Printing::Value(&self.car)];
+        while let Some(printing) = pending.pop() {
+            match printing {
+                Printing::Value(Token::Pair(pair)) => {
+                    write!(f, "(")?;
+                    pending.push(Printing::Rest(&pair.cdr));
+                    pending.push(Printing::Value(&pair.car));
+                }
+                Printing::Value(value) => write!(f, "{}", value)?,
+                Printing::Rest(Token::Pair(pair)) => {
+                    write!(f, " ")?;
+                    pending.push(Printing::Rest(&pair.cdr));
+                    pending.push(Printing::Value(&pair.car));
+                }
+                Printing::Rest(Token::Nil) => write!(f, ")")?,
+                Printing::Rest(tail) => {
+                    write!(f, " . ")?;
+                    pending.push(Printing::Text(")"));
+                    pending.push(Printing::Value(tail));
+                }
+                Printing::Text(text) => write!(f, "{}", text)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Pairs are debugged the same way they are printed, which doesn't recurse.
+impl Debug for Pair {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Pair{}", self)
+    }
+}
@@ -1,36 +1,47 @@
 //! This module holds the syntax tree
 
-use std::borrow::Borrow;
+use std::{
+    fmt::{self, Display, Formatter},
+    rc::Rc,
+};
 
-use crate::logic::BuiltinFuncs;
+use crate::{
+    error::AtlasError,
+    logic::{self, env::Environment, Callable},
+};
 
-#[derive(Debug)]
+use self::{lexer::Span, list::Pair, number::Number, symbol::Symbol};
+
+/// This module holds the lexer, which splits the source code into lexemes.
+pub mod lexer;
+/// This module holds cons cells, the pairs lists are built from.
+pub mod list;
+/// This module holds the numeric tower: exact integers and rationals, and inexact floats.
+pub mod number;
+/// This module holds the reader, which builds the syntax tree out of the lexemes.
+pub mod reader;
+/// This module holds symbols, the names used as data, as in 'name.
+pub mod symbol;
+
+#[derive(Debug, Default)]
 pub struct Tree {
-    /// Holds an array of expressions
-    pub(crate) expressions: Vec<TokenExpression>,
+    /// Holds the top level forms, in the order they were written. Expressions hold their
+    /// nested expressions.
+    pub(crate) forms: Vec<Token>,
 }
 
 impl Tree {
     /// Create a new syntax tree.
     pub fn new() -> Self {
-        Tree {
-            expressions: Vec::new(),
-        }
+        Tree { forms: Vec::new() }
     }
-    /// push a new expression to the tree vec
-    pub fn push(&mut self, expr: TokenExpression) {
-        self.expressions.push(expr);
+    /// push a new top level form to the tree vec
+    pub fn push(&mut self, form: Token) {
+        self.forms.push(form);
     }
-    /// get expression from index (mutable)
-    pub fn peek_mut(&mut self, index: usize) -> &mut TokenExpression {
-        self.expressions.get_mut(index).unwrap()
-    }
-    /// get expression from index
-    pub fn peek(&self, index: usize) -> &TokenExpression {
-        match self.expressions.get(index) {
-            Some(expression) => expression,
-            None => self.expressions.get(index - 1).unwrap(),
-        }
+    /// iterate over the top level forms, in the order they were written
+    pub fn iter(&self) -> impl Iterator<Item = &Token> {
+        self.forms.iter()
     }
 }
 
@@ -40,33 +51,98 @@ impl Tree {
 //                                                                Token  ⤶
 // Will have the same lifetime as the rest of the expression.
 #[derive(Debug)]
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 pub enum Token {
     // Function keywords.
     // Keyword(String),
     // A string literal (unquoted)
     Literal(String),
-    // a number literal
-    Number(f64),
-    // TODO
-    Variable(String), //(PhantomData<&'a ()>),
-    // Might hold a reference to another expression to eval. (depth, index)
-    Expression((usize, usize)),
-    Result,
+    // a number literal, exact or inexact
+    Number(Number),
+    // A name, resolved to its value in the current environment when reduced
+    Variable(String),
+    // Holds a nested expression to eval
+    Expression(Rc<TokenExpression>),
+    // A boolean value, as returned by conditionals
+    Boolean(bool),
+    // The empty value. Together with Boolean(false) it is the only false value.
+    Nil,
+    // A builtin or user defined function, as returned by lambda
+    Procedure(Callable),
+    // A cons cell, shared between every list it is part of
+    Pair(Rc<Pair>),
+    // A name used as data, as returned by quote. Unlike variables, symbols are not looked up.
+    Symbol(Symbol),
+}
+
+impl Token {
+    /// Reduce this token to its value, as if it was written at the top level.
+    pub fn reduce(&self, env: &Environment) -> Result<Token, AtlasError> {
+        logic::reduce_token(self, env)
+    }
+    /// Get a human readable name for the type of this token, as used in error messages.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Token::Literal(_) => "string literal",
+            Token::Number(_) => "number",
+            Token::Variable(_) => "variable",
+            Token::Expression(_) => "expression",
+            Token::Boolean(_) => "boolean",
+            Token::Nil => "nil",
+            Token::Procedure(_) => "procedure",
+            Token::Pair(_) => "pair",
+            Token::Symbol(_) => "symbol",
+        }
+    }
+    /// Checks if this token counts as true in a conditional. Everything but false and nil does.
+    pub fn is_truthy(&self) -> bool {
+        !matches!(self, Token::Boolean(false) | Token::Nil)
+    }
+    /// Render this token for humans. Unlike Display, string literals are not quoted.
+    pub fn to_display_string(&self) -> String {
+        match self {
+            Token::Literal(string) => string.clone(),
+            _ => self.to_string(),
+        }
+    }
 }
-/// try to find a keyword for this string else return a variable if it is unquoted
-/// , a literal if it has "" quotes or a number if it is a number.
+
+/// Turn an atom into a boolean if it is #t or #f, nil, a number if it is one, or else a variable.
+/// String literals are lexed on their own, so they never get here.
 impl From<String> for Token {
     fn from(parsed: String) -> Token {
-        // quoted literal
-        if parsed.starts_with('"') && parsed.ends_with('"') {
-            // get rid of the quotes and return a string literal
-            Token::Literal(parsed.replace('"', "").to_string())
-        // number literal as f64 representation
-        } else if let Ok(digit) = parsed.parse() {
-            Token::Number(digit)
-        } else {
-            Token::Variable(parsed.to_string())
+        match parsed.as_str() {
+            "#t" | "#true" => return Token::Boolean(true),
+            "#f" | "#false" => return Token::Boolean(false),
+            "nil" => return Token::Nil,
+            _ => (),
+        }
+        // number literal, see Number::from_str for its syntax
+        match parsed.parse() {
+            Ok(number) => Token::Number(number),
+            Err(_) => Token::Variable(parsed),
+        }
+    }
+}
+
+/// Print tokens the same way they are written in the source. Procedures can't be written, so
+/// they print as #<procedure name>.
+impl Display for Token {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Literal(string) => write!(f, "{:?}", string),
+            Token::Number(number) => write!(f, "{}", number),
+            Token::Variable(name) => write!(f, "{}", name),
+            Token::Expression(exp) => write!(f, "{}", exp),
+            Token::Boolean(true) => write!(f, "#t"),
+            Token::Boolean(false) => write!(f, "#f"),
+            Token::Nil => write!(f, "nil"),
+            Token::Procedure(Callable::User(function)) if function.name.is_none() => {
+                write!(f, "#<procedure>")
+            }
+            Token::Procedure(callable) => write!(f, "#<procedure {}>", callable.name()),
+            Token::Pair(pair) => write!(f, "{}", pair),
+            Token::Symbol(symbol) => write!(f, "{}", symbol),
         }
     }
 }
@@ -75,6 +151,7 @@ impl From<String> for Token {
 pub enum Symbols {
     LPAREN,
     RPAREN,
+    QUOTE,
 }
 
 impl TryFrom<char> for Symbols {
@@ -83,13 +160,13 @@ impl TryFrom<char> for Symbols {
         match value {
             '(' => Ok(Self::LPAREN),
             ')' => Ok(Self::RPAREN),
+            '"' => Ok(Self::QUOTE),
             _ => Err("Could not convert char to a known symbol"),
         }
     }
 }
 
-#[derive(Debug)]
-#[allow(dead_code)]
+#[derive(Debug, Default, PartialEq)]
 /// A group of tokens and arguments.
 pub struct TokenExpression {
     // Keyword for this expression
@@ -100,7 +177,6 @@ pub struct TokenExpression {
     pub args: Vec<Token>,
     // holds the location of both of this expression's delimiters
     pub delimiters: (Option<usize>, Option<usize>),
-    pub index: usize,
 }
 
 impl TokenExpression {
@@ -111,20 +187,25 @@ impl TokenExpression {
             depth: 0,
             args: Vec::new(),
             delimiters: (None, None),
-            index: 0,
         }
     }
-    /// Reduce this expression to a Token result
-    pub fn reduce(&mut self) -> Token {
-        let result = BuiltinFuncs::exec(self.borrow());
-        match result {
-            Ok(result) => result,
-            Err(err) => {
-                // TEMP: don't panic
-                panic!("Reducing failed with error: {}", err)
-            }
+    /// Reduce this expression to a Token result.
+    pub fn reduce(&self, env: &Environment) -> Result<Token, AtlasError> {
+        logic::reduce_expression(self, env)
+    }
+    /// Get the span of this expression in the source, from its opening paren to its closing one.
+    /// Unclosed expressions only span their opening paren.
+    pub fn span(&self) -> Option<Span> {
+        match self.delimiters {
+            (Some(opening), Some(closing)) => Some((opening, closing + 1)),
+            (Some(opening), None) => Some((opening, opening + 1)),
+            _ => None,
         }
     }
+    /// Iterate over every token of this expression, keyword included.
+    pub fn items(&self) -> impl Iterator<Item = &Token> {
+        self.keyword.iter().chain(self.args.iter())
+    }
     /// Checks if the current expression has both its delimiters. Note, it does not mean it's empty,
     /// this function only returns true if the left delimiter "(" is present, but not the closing delimiter.
     pub fn is_unclosed(&self) -> bool {
@@ -147,3 +228,11 @@ impl TokenExpression {
         self.delimiters.1
     }
 }
+
+/// Print expressions the same way they are written in the source.
+impl Display for TokenExpression {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let items: Vec<String> = self.items().map(Token::to_string).collect();
+        write!(f, "({})", items.join(" "))
+    }
+}
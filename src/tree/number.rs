@@ -0,0 +1,363 @@
+//! This module holds the numeric tower: exact integers and rationals, and inexact floats.
+
+use std::{
+    cmp::Ordering,
+    fmt::{self, Display, Formatter},
+    ops::{Add, Mul, Neg, Sub},
+    str::FromStr,
+};
+
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_rational::BigRational;
+use num_traits::{pow, One, Signed, ToPrimitive, Zero};
+
+/// How many bits an exact power can take, about 315 thousand digits. Larger powers would take
+/// too long to compute exactly, so they are computed with floats instead.
+pub const MAX_EXACT_POWER_BITS: u64 = 1 << 20;
+
+/// A number. Operations between two exact numbers give an exact result, and any operation
+/// involving a float gives a float.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Number {
+    // An integer of arbitrary precision
+    Integer(BigInt),
+    // A fraction, always in its lowest terms and with a denominator other than 1
+    Rational(BigRational),
+    // A floating point number
+    Float(f64),
+}
+
+// Two numbers converted to the type both can be represented with.
+enum Promoted {
+    Integers(BigInt, BigInt),
+    Rationals(BigRational, BigRational),
+    Floats(f64, f64),
+}
+
+impl Number {
+    /// The exact number 0, the identity of addition.
+    pub fn zero() -> Self {
+        Number::Integer(BigInt::zero())
+    }
+    /// The exact number 1, the identity of multiplication.
+    pub fn one() -> Self {
+        Number::Integer(BigInt::one())
+    }
+    /// Create a number out of a fraction, turning it into an integer if its denominator is 1.
+    pub fn rational(fraction: BigRational) -> Self {
+        if fraction.is_integer() {
+            Number::Integer(fraction.to_integer())
+        } else {
+            Number::Rational(fraction)
+        }
+    }
+    /// Checks if this number is exact, so it is an integer or a rational.
+    pub fn is_exact(&self) -> bool {
+        !matches!(self, Number::Float(_))
+    }
+    /// Checks if this number is zero.
+    pub fn is_zero(&self) -> bool {
+        match self {
+            Number::Integer(integer) => integer.is_zero(),
+            Number::Rational(fraction) => fraction.is_zero(),
+            Number::Float(float) => *float == 0.0,
+        }
+    }
+    /// Get the closest float to this number.
+    pub fn to_f64(&self) -> f64 {
+        match self {
+            Number::Integer(integer) => integer.to_f64().unwrap_or(f64::NAN),
+            Number::Rational(fraction) => fraction.to_f64().unwrap_or(f64::NAN),
+            Number::Float(float) => *float,
+        }
+    }
+    // get this number as a fraction. Floats can't be.
+    fn to_rational(&self) -> Option<BigRational> {
+        match self {
+            Number::Integer(integer) => Some(BigRational::from_integer(integer.clone())),
+            Number::Rational(fraction) => Some(fraction.clone()),
+            Number::Float(_) => None,
+        }
+    }
+    // convert both numbers to the least general type that can represent them.
+    fn promote(&self, other: &Number) -> Promoted {
+        match (self, other) {
+            (Number::Integer(a), Number::Integer(b)) => Promoted::Integers(a.clone(), b.clone()),
+            (Number::Float(_), _) | (_, Number::Float(_)) => {
+                Promoted::Floats(self.to_f64(), other.to_f64())
+            }
+            _ => match (self.to_rational(), other.to_rational()) {
+                (Some(a), Some(b)) => Promoted::Rationals(a, b),
+                _ => Promoted::Floats(self.to_f64(), other.to_f64()),
+            },
+        }
+    }
+    /// Divide two numbers. Exact numbers give an exact result, so (/ 1 3) is 1/3.
+    /// Returns None when dividing by an exact zero, which has no result.
+    pub fn checked_div(&self, other: &Number) -> Option<Number> {
+        match self.promote(other) {
+            Promoted::Integers(_, b) if b.is_zero() => None,
+            Promoted::Rationals(_, b) if b.is_zero() => None,
+            Promoted::Integers(a, b) => Some(Number::rational(BigRational::new(a, b))),
+            Promoted::Rationals(a, b) => Some(Number::rational(a / b)),
+            Promoted::Floats(a, b) => Some(Number::Float(a / b)),
+        }
+    }
+    /// Checks if this number is an integer, exact or not.
+    pub fn is_integer(&self) -> bool {
+        match self {
+            Number::Integer(_) => true,
+            Number::Rational(_) => false,
+            Number::Float(float) => float.fract() == 0.0,
+        }
+    }
+    /// Get the absolute value of this number.
+    pub fn abs(&self) -> Number {
+        match self {
+            Number::Integer(integer) => Number::Integer(integer.abs()),
+            Number::Rational(fraction) => Number::Rational(fraction.abs()),
+            Number::Float(float) => Number::Float(float.abs()),
+        }
+    }
+    /// Get the largest integer not greater than this number.
+    pub fn floor(&self) -> Number {
+        self.round_with(BigRational::floor, f64::floor)
+    }
+    /// Get the smallest integer not smaller than this number.
+    pub fn ceil(&self) -> Number {
+        self.round_with(BigRational::ceil, f64::ceil)
+    }
+    /// Get the integer part of this number, rounding towards zero.
+    pub fn truncate(&self) -> Number {
+        self.round_with(BigRational::trunc, f64::trunc)
+    }
+    /// Get the closest integer to this number, rounding halves to the even integer.
+    pub fn round(&self) -> Number {
+        self.round_with(
+            |fraction| {
+                let floor = fraction.floor();
+                let diff = fraction - &floor;
+                let half = BigRational::new(BigInt::one(), BigInt::from(2));
+                if diff < half || (diff == half && floor.to_integer().is_even()) {
+                    floor
+                } else {
+                    floor + BigRational::one()
+                }
+            },
+            f64::round_ties_even,
+        )
+    }
+    // round this number with the given functions. Integers are already rounded, and rounding
+    // keeps floats inexact.
+    fn round_with(
+        &self,
+        exact: fn(&BigRational) -> BigRational,
+        inexact: fn(f64) -> f64,
+    ) -> Number {
+        match self {
+            Number::Integer(_) => self.clone(),
+            Number::Rational(fraction) => Number::rational(exact(fraction)),
+            Number::Float(float) => Number::Float(inexact(*float)),
+        }
+    }
+    /// Get the square root of this number. It is exact if this number is exact and the root of
+    /// both its numerator and denominator are integers. Negative numbers give NaN.
+    pub fn sqrt(&self) -> Number {
+        let exact_root = |integer: &BigInt| {
+            let root = integer.sqrt();
+            (&root * &root == *integer).then_some(root)
+        };
+        if let Some(fraction) = self
+            .to_rational()
+            .filter(|fraction| !fraction.is_negative())
+        {
+            if let (Some(numerator), Some(denominator)) =
+                (exact_root(fraction.numer()), exact_root(fraction.denom()))
+            {
+                return Number::rational(BigRational::new(numerator, denominator));
+            }
+        }
+        Number::Float(self.to_f64().sqrt())
+    }
+    /// Raise this number to the given power. Exact numbers raised to an exact integer give an
+    /// exact result, unless it would take more than MAX_EXACT_POWER_BITS bits to hold, in which
+    /// case the result is inexact. Returns None when raising an exact zero to a negative power.
+    pub fn pow(&self, exponent: &Number) -> Option<Number> {
+        match (self.to_rational(), exponent) {
+            (Some(base), Number::Integer(exponent)) => {
+                // 0, 1 and -1 stay small whatever the exponent, only its parity matters
+                if base.is_zero() || base.abs().is_one() {
+                    if base.is_zero() && exponent.is_negative() {
+                        return None;
+                    }
+                    let parity = if exponent.is_zero() {
+                        0
+                    } else if exponent.is_even() {
+                        2
+                    } else {
+                        1
+                    };
+                    return Some(Number::rational(pow(base, parity)));
+                }
+                // the result takes about as many bits as the base times the exponent
+                let bits = base.numer().bits().max(base.denom().bits());
+                let magnitude = match exponent.abs().to_u64() {
+                    Some(magnitude) if bits.saturating_mul(magnitude) <= MAX_EXACT_POWER_BITS => {
+                        magnitude
+                    }
+                    _ => return Some(Number::Float(self.to_f64().powf(exponent.to_f64()?))),
+                };
+                // powers of a fraction in its lowest terms are still in their lowest terms, so
+                // both sides are raised on their own, without reducing after every step
+                let magnitude = magnitude as u32;
+                let result =
+                    BigRational::new_raw(base.numer().pow(magnitude), base.denom().pow(magnitude));
+                if exponent.is_negative() {
+                    return Some(Number::rational(result.recip()));
+                }
+                Some(Number::rational(result))
+            }
+            _ => Some(Number::Float(self.to_f64().powf(exponent.to_f64()))),
+        }
+    }
+    /// Compare the values of two numbers, whatever their types are. NaN is not ordered.
+    pub fn compare(&self, other: &Number) -> Option<Ordering> {
+        match self.promote(other) {
+            Promoted::Integers(a, b) => Some(a.cmp(&b)),
+            Promoted::Rationals(a, b) => Some(a.cmp(&b)),
+            Promoted::Floats(a, b) => a.partial_cmp(&b),
+        }
+    }
+}
+
+// implement an arithmetic operator for numbers, promoting both operands first.
+macro_rules! impl_arithmetic {
+    ($trait:ident, $method:ident) => {
+        impl $trait for Number {
+            type Output = Number;
+            fn $method(self, other: Number) -> Number {
+                match self.promote(&other) {
+                    Promoted::Integers(a, b) => Number::Integer(a.$method(b)),
+                    Promoted::Rationals(a, b) => Number::rational(a.$method(b)),
+                    Promoted::Floats(a, b) => Number::Float(a.$method(b)),
+                }
+            }
+        }
+    };
+}
+
+impl_arithmetic!(Add, add);
+impl_arithmetic!(Sub, sub);
+impl_arithmetic!(Mul, mul);
+
+impl Neg for Number {
+    type Output = Number;
+    fn neg(self) -> Number {
+        match self {
+            Number::Integer(integer) => Number::Integer(-integer),
+            Number::Rational(fraction) => Number::Rational(-fraction),
+            Number::Float(float) => Number::Float(-float),
+        }
+    }
+}
+
+impl From<i64> for Number {
+    fn from(integer: i64) -> Self {
+        Number::Integer(BigInt::from(integer))
+    }
+}
+
+impl From<f64> for Number {
+    fn from(float: f64) -> Self {
+        Number::Float(float)
+    }
+}
+
+// parse the digits of an integer in the given radix, without a sign.
+fn parse_digits(digits: &str, radix: u32) -> Option<BigInt> {
+    if digits.is_empty() || !digits.chars().all(|char| char.is_digit(radix)) {
+        return None;
+    }
+    BigInt::parse_bytes(digits.as_bytes(), radix)
+}
+
+/// Parse number literals. Integers can be written in decimal, or in hexadecimal, binary or octal
+/// with the #x, #b and #o prefixes (or 0x, 0b and 0o). Rationals are written as 1/3, and floats
+/// as 1.5, .5, 1e10 or 1.5e-3. Infinities and NaN are written as +inf.0, -inf.0 and +nan.0.
+impl FromStr for Number {
+    type Err = &'static str;
+    fn from_str(literal: &str) -> Result<Self, Self::Err> {
+        match literal {
+            "+inf.0" => return Ok(Number::Float(f64::INFINITY)),
+            "-inf.0" => return Ok(Number::Float(f64::NEG_INFINITY)),
+            "+nan.0" | "-nan.0" => return Ok(Number::Float(f64::NAN)),
+            _ => (),
+        }
+        let invalid = "Not a number literal";
+        let (negative, unsigned) = match literal.strip_prefix('-') {
+            Some(unsigned) => (true, unsigned),
+            None => (false, literal.strip_prefix('+').unwrap_or(literal)),
+        };
+        let sign = |integer: BigInt| if negative { -integer } else { integer };
+        for (prefixes, radix) in [(["#x", "0x"], 16), (["#b", "0b"], 2), (["#o", "0o"], 8)] {
+            for prefix in prefixes {
+                if let Some(digits) = unsigned.strip_prefix(prefix) {
+                    let integer = parse_digits(digits, radix).ok_or(invalid)?;
+                    return Ok(Number::Integer(sign(integer)));
+                }
+            }
+        }
+        if let Some((numerator, denominator)) = unsigned.split_once('/') {
+            let numerator = parse_digits(numerator, 10).ok_or(invalid)?;
+            let denominator = parse_digits(denominator, 10).ok_or(invalid)?;
+            if denominator.is_zero() {
+                return Err("Rational literals can't have a zero denominator");
+            }
+            return Ok(Number::rational(BigRational::new(
+                sign(numerator),
+                denominator,
+            )));
+        }
+        if let Some(integer) = parse_digits(unsigned, 10) {
+            return Ok(Number::Integer(sign(integer)));
+        }
+        // only accept what looks like a float, so names like "inf" or "nan" are not numbers.
+        // The only sign allowed past the first one is the one of the exponent.
+        let (mantissa, exponent) = match unsigned.split_once(['e', 'E']) {
+            Some((mantissa, exponent)) => (mantissa, Some(exponent)),
+            None => (unsigned, None),
+        };
+        let float_like = mantissa.chars().any(|char| char.is_ascii_digit())
+            && mantissa
+                .chars()
+                .all(|char| char.is_ascii_digit() || char == '.')
+            && exponent.is_none_or(|exponent| {
+                parse_digits(exponent.strip_prefix(['+', '-']).unwrap_or(exponent), 10).is_some()
+            });
+        match unsigned.parse::<f64>() {
+            Ok(float) if float_like => Ok(Number::Float(if negative { -float } else { float })),
+            _ => Err(invalid),
+        }
+    }
+}
+
+/// Print numbers the same way they are written in the source. Floats always have a decimal
+/// point or an exponent, so they can't be mistaken for integers.
+impl Display for Number {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Number::Integer(integer) => write!(f, "{}", integer),
+            Number::Rational(fraction) => write!(f, "{}", fraction),
+            Number::Float(float) if float.is_nan() => write!(f, "+nan.0"),
+            Number::Float(float) if float.is_infinite() => {
+                write!(
+                    f,
+                    "{}inf.0",
+                    if float.is_sign_positive() { "+" } else { "-" }
+                )
+            }
+            Number::Float(float) => write!(f, "{:?}", float),
+        }
+    }
+}
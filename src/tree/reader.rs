@@ -0,0 +1,191 @@
+//! This module holds the reader, which builds the syntax tree out of the lexemes.
+
+use std::{iter::Peekable, rc::Rc};
+
+use crate::{error::AtlasError, DEFAULT_MAX_DEPTH};
+
+use super::{
+    lexer::{Lexeme, Lexer, Span, SpannedLexeme},
+    Token, TokenExpression, Tree,
+};
+
+/// Builds expressions by recursive descent over the lexemes of some source code.
+pub struct Reader<I: Iterator<Item = Result<SpannedLexeme, AtlasError>>> {
+    lexemes: Peekable<I>,
+    // how deeply forms can be nested, so reading fails before the stack overflows
+    max_depth: usize,
+}
+
+impl<'a> Reader<Lexer<'a>> {
+    /// Create a new reader for the given source code.
+    pub fn new(source: &'a str) -> Self {
+        Reader::new_at(source, 0)
+    }
+    /// Create a new reader for the source code starting at the given byte offset. Spans are
+    /// still offsets into the whole source.
+    pub fn new_at(source: &'a str, start: usize) -> Self {
+        Reader {
+            lexemes: Lexer::new_at(source, start).peekable(),
+            max_depth: DEFAULT_MAX_DEPTH,
+        }
+    }
+}
+
+impl<I: Iterator<Item = Result<SpannedLexeme, AtlasError>>> Reader<I> {
+    /// Change how deeply forms can be nested. Quotes and datum comments count as a level too.
+    pub fn set_max_depth(&mut self, limit: usize) {
+        self.max_depth = limit;
+    }
+    /// Read every top level form into a new tree.
+    pub fn read_tree(&mut self) -> Result<Tree, AtlasError> {
+        let mut tree = Tree::new();
+        while let Some(spanned) = self.lexemes.next() {
+            if let Some(form) = self.read_form(spanned?, 0)? {
+                tree.push(form);
+            }
+        }
+        Ok(tree)
+    }
+
+    // read the form starting with the given lexeme. Returns None for a datum comment, after
+    // skipping the form it comments out.
+    fn read_form(
+        &mut self,
+        spanned: SpannedLexeme,
+        depth: usize,
+    ) -> Result<Option<Token>, AtlasError> {
+        if depth >= self.max_depth {
+            return Err(AtlasError::recursion_limit(self.max_depth).with_span(spanned.span));
+        }
+        let form = match spanned.lexeme {
+            Lexeme::LParen => {
+                Token::Expression(Rc::new(self.read_expression(spanned.span, depth)?))
+            }
+            Lexeme::Str(string) => Token::Literal(string),
+            Lexeme::Atom(atom) => Token::from(atom),
+            Lexeme::RParen => {
+                return Err(
+                    AtlasError::parse("unexpected closing delimiter `)`").with_span(spanned.span)
+                )
+            }
+            Lexeme::DatumComment => {
+                self.following_form(spanned.span, depth + 1, "#;")?;
+                return Ok(None);
+            }
+            Lexeme::Abbreviation(name) => {
+                let (form, end) = self.following_form(spanned.span, depth + 1, written(name))?;
+                let mut exp = abbreviation(name, form, (spanned.span.0, end));
+                exp.depth = depth;
+                Token::Expression(Rc::new(exp))
+            }
+        };
+        Ok(Some(form))
+    }
+
+    // read the form following a prefix at the given span, such as a datum comment or a quote.
+    // Returns it together with the offset it ends at.
+    fn following_form(
+        &mut self,
+        prefix: Span,
+        depth: usize,
+        written: &str,
+    ) -> Result<(Token, usize), AtlasError> {
+        let missing =
+            || AtlasError::parse(format!("expected a form after `{}`", written)).with_span(prefix);
+        loop {
+            let spanned = match self.lexemes.next_if(|next| {
+                !matches!(
+                    next,
+                    Ok(SpannedLexeme {
+                        lexeme: Lexeme::RParen,
+                        ..
+                    })
+                )
+            }) {
+                Some(spanned) => spanned?,
+                None => return Err(missing()),
+            };
+            let end = spanned.span.1;
+            // a datum comment in between is skipped together with the form after it
+            match self.read_form(spanned, depth)? {
+                Some(Token::Expression(exp)) => {
+                    let end = exp.span().map_or(end, |span| span.1);
+                    return Ok((Token::Expression(exp), end));
+                }
+                Some(form) => return Ok((form, end)),
+                None => (),
+            }
+        }
+    }
+
+    // read the rest of an expression whose opening paren has already been consumed. The first
+    // token of an expression is its keyword, the rest are arguments.
+    fn read_expression(
+        &mut self,
+        opening: Span,
+        depth: usize,
+    ) -> Result<TokenExpression, AtlasError> {
+        let mut exp = TokenExpression::new();
+        exp.insert_opening(opening.0);
+        exp.depth = depth;
+        // how many items came before the dot of a dotted list, as in (a b . c), and its span
+        let mut dot: Option<(usize, Span)> = None;
+        while let Some(spanned) = self.lexemes.next() {
+            let spanned = spanned?;
+            let items = usize::from(exp.keyword.is_some()) + exp.args.len();
+            if spanned.lexeme == Lexeme::RParen {
+                match dot {
+                    // the dot must be followed by exactly one item
+                    Some((before, span)) if items != before + 2 => return Err(misplaced_dot(span)),
+                    _ => (),
+                }
+                exp.insert_closing(spanned.span.0);
+                return Ok(exp);
+            }
+            if matches!(&spanned.lexeme, Lexeme::Atom(atom) if atom == ".") {
+                // the dot must come after at least one item, and only once
+                if items == 0 || dot.is_some() {
+                    return Err(misplaced_dot(spanned.span));
+                }
+                dot = Some((items, spanned.span));
+            }
+            let token = match self.read_form(spanned, depth + 1)? {
+                Some(token) => token,
+                None => continue,
+            };
+            if exp.keyword.is_none() && exp.args.is_empty() {
+                exp.keyword = Some(token);
+            } else {
+                exp.args.push(token);
+            }
+        }
+        // the input ended before this expression was closed
+        Err(AtlasError::parse("unclosed delimiter `(`").with_span(opening))
+    }
+}
+
+// the error for a dot anywhere but right before the last item of a list.
+fn misplaced_dot(span: Span) -> AtlasError {
+    AtlasError::parse("unexpected `.`, a dot can only come before the last item of a list")
+        .with_span(span)
+}
+
+// get how the abbreviation of a special form is written.
+fn written(name: &str) -> &'static str {
+    match name {
+        "quote" => "'",
+        "quasiquote" => "`",
+        "unquote" => ",",
+        _ => ",@",
+    }
+}
+
+// build the expression an abbreviation is short for, as in (quote x) for 'x, spanning both.
+fn abbreviation(name: &str, form: Token, span: Span) -> TokenExpression {
+    let mut exp = TokenExpression::new();
+    exp.keyword = Some(Token::Variable(name.to_string()));
+    exp.args.push(form);
+    exp.insert_opening(span.0);
+    exp.insert_closing(span.1 - 1);
+    exp
+}
@@ -0,0 +1,58 @@
+//! This module holds symbols, the names used as data, as in 'name.
+
+use std::{
+    cell::RefCell,
+    collections::HashSet,
+    fmt::{self, Display, Formatter},
+    hash::{Hash, Hasher},
+    rc::Rc,
+};
+
+thread_local! {
+    // every symbol created so far, so symbols with the same name share it
+    static SYMBOLS: RefCell<HashSet<Rc<str>>> = RefCell::new(HashSet::new());
+}
+
+/// An interned name. Symbols with the same name share the same string, so they are compared
+/// in constant time, no matter how long their names are.
+#[derive(Debug, Clone, Eq)]
+pub struct Symbol(Rc<str>);
+
+impl Symbol {
+    /// Get the symbol with the given name, creating it if there isn't one yet.
+    pub fn new(name: &str) -> Self {
+        SYMBOLS.with(|symbols| {
+            let mut symbols = symbols.borrow_mut();
+            match symbols.get(name) {
+                Some(interned) => Symbol(interned.clone()),
+                None => {
+                    let interned: Rc<str> = Rc::from(name);
+                    symbols.insert(interned.clone());
+                    Symbol(interned)
+                }
+            }
+        })
+    }
+    /// Get the name of this symbol.
+    pub fn name(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq for Symbol {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Hash for Symbol {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Rc::as_ptr(&self.0).hash(state);
+    }
+}
+
+impl Display for Symbol {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
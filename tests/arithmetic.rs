@@ -0,0 +1,72 @@
+mod common;
+
+use atlas::error::AtlasError;
+use common::{eval, run};
+
+#[test]
+fn exact_powers_stay_exact() {
+    assert_eq!(eval("(expt 2 10)"), "1024");
+    assert_eq!(eval("(expt 2/3 -3)"), "27/8");
+    assert_eq!(eval("(expt -2 -3)"), "-1/8");
+    assert_eq!(eval("(expt 0 0)"), "1");
+}
+
+// these used to be computed exactly, which never finished
+#[test]
+fn huge_powers_are_inexact() {
+    assert_eq!(eval("(expt 2 100000000000)"), "+inf.0");
+    assert_eq!(eval("(expt 2 -100000000000)"), "0.0");
+    assert_eq!(eval("(expt 7 100000000000000000000000000)"), "+inf.0");
+}
+
+#[test]
+fn powers_of_zero_and_one_are_exact_whatever_the_exponent() {
+    assert_eq!(eval("(expt 1 100000000000)"), "1");
+    assert_eq!(eval("(expt -1 100000000001)"), "-1");
+    assert_eq!(eval("(expt -1 -100000000000000000000000000)"), "1");
+    assert_eq!(eval("(expt 0 100000000000000000000000)"), "0");
+}
+
+#[test]
+fn minus_and_divide_with_one_argument_negate_and_invert() {
+    assert_eq!(eval("(- 5)"), "-5");
+    assert_eq!(eval("(/ 2)"), "1/2");
+}
+
+#[test]
+fn minus_and_divide_need_an_argument() {
+    for source in ["(-)", "(/)"] {
+        let err = run(source).unwrap_err();
+        assert!(
+            matches!(err, AtlasError::Arity { .. }),
+            "unexpected error: {:?}",
+            err
+        );
+    }
+}
+
+#[test]
+fn dividing_by_an_exact_zero_fails() {
+    let err = run("(/ 1 0)").unwrap_err();
+    assert_eq!(err.to_string(), "division by zero");
+}
+
+#[test]
+fn dividing_by_an_inexact_zero_gives_an_infinity() {
+    assert_eq!(eval("(/ 1.0 0)"), "+inf.0");
+    assert_eq!(eval("(/ 1 0.0)"), "+inf.0");
+}
+
+#[test]
+fn non_number_arguments_are_reported_by_position() {
+    let err = run("(+ 1 \"a\")").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "mismatched types in argument 2 of `+`: expected a number, found string literal `\"a\"`"
+    );
+    let err = run("(- \"a\" 1)").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "mismatched types in argument 1 of `-`: expected a number, found string literal `\"a\"`"
+    );
+}
@@ -0,0 +1,50 @@
+mod common;
+
+use atlas::{error::AtlasError, Parser};
+use common::{eval, run};
+
+// parse some source code, expecting it to be rejected
+fn parse_error(source: &str) -> AtlasError {
+    Parser::new().parse(source.to_string()).unwrap_err()
+}
+
+#[test]
+fn line_comments_go_on_until_the_end_of_the_line() {
+    assert_eq!(eval("(+ 1 ; two\n 2) ; three"), "3");
+    assert_eq!(eval("(+ 1 ; two\r 2)"), "3");
+    assert_eq!(run("; only a comment").unwrap(), None);
+}
+
+#[test]
+fn block_comments_can_be_nested() {
+    assert_eq!(eval("#| a #| b |# c |# (+ 1 2)"), "3");
+    assert_eq!(eval("(+ 1 #| x\n y |#2)"), "3");
+}
+
+#[test]
+fn unterminated_block_comments_point_at_their_opening() {
+    let err = parse_error("(+ 1 2) #| a #| b |# (+ 1 2)");
+    assert_eq!(err.to_string(), "unterminated block comment");
+    assert_eq!(err.span(), Some((8, 10)));
+}
+
+#[test]
+fn datum_comments_skip_the_next_form() {
+    assert_eq!(eval("(+ 1 #;2)"), "1");
+    assert_eq!(eval("(+ 1 #;(* 2 3) 4)"), "5");
+    assert_eq!(eval("(+ 1 #;'x 2)"), "3");
+    assert_eq!(eval("(+ 1 #;#;2 3 4)"), "5");
+    assert_eq!(eval("#;(print 1) (+ 1 2)"), "3");
+}
+
+#[test]
+fn datum_comments_need_a_form_to_skip() {
+    // before a closing parenthesis
+    let err = parse_error("(+ 1 #;)");
+    assert_eq!(err.to_string(), "expected a form after `#;`");
+    assert_eq!(err.span(), Some((5, 7)));
+    // at the end of the input
+    let err = parse_error("(+ 1 2) #;");
+    assert_eq!(err.to_string(), "expected a form after `#;`");
+    assert_eq!(err.span(), Some((8, 10)));
+}
@@ -0,0 +1,44 @@
+//! Helpers shared by the integration tests.
+// every test file compiles this module, but not every one uses all of it
+#![allow(dead_code)]
+
+use std::{cell::RefCell, io::Write, rc::Rc};
+
+use atlas::{error::AtlasError, tree::Token, Parser};
+
+/// A writer whose contents can still be read after handing it to a parser.
+#[derive(Clone, Default)]
+pub struct Buffer(Rc<RefCell<Vec<u8>>>);
+
+impl Buffer {
+    /// Get everything written so far.
+    pub fn contents(&self) -> String {
+        String::from_utf8(self.0.borrow().clone()).unwrap()
+    }
+}
+
+impl Write for Buffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Parse and reduce some source code with a new parser, returning the value of its last form.
+pub fn run(source: &str) -> Result<Option<Token>, AtlasError> {
+    let mut parser = Parser::new();
+    parser.set_output(Buffer::default());
+    parser.parse(source.to_string())?;
+    parser.reduce_all()
+}
+
+/// Like run, but get the value of the last form printed, failing the test on errors.
+pub fn eval(source: &str) -> String {
+    match run(source) {
+        Ok(Some(value)) => value.to_string(),
+        Ok(None) => String::new(),
+        Err(err) => panic!("`{}` failed: {}", source, err),
+    }
+}
@@ -0,0 +1,52 @@
+mod common;
+
+use common::{eval, run};
+
+#[test]
+fn comparisons_chain_over_every_argument() {
+    assert_eq!(eval("(< 1 2 3)"), "#t");
+    assert_eq!(eval("(< 1 3 2)"), "#f");
+    assert_eq!(eval("(<= 1 1 2)"), "#t");
+    assert_eq!(eval("(> 3 2 1)"), "#t");
+    assert_eq!(eval("(>= 3 3 4)"), "#f");
+    assert_eq!(eval("(= 1 1 1.0 2/2)"), "#t");
+    assert_eq!(eval("(< 1)"), "#t");
+    assert_eq!(eval("(< \"a\" \"b\" \"c\")"), "#t");
+}
+
+#[test]
+fn numbers_and_strings_are_not_compared_together() {
+    assert_eq!(
+        run("(< 1 \"a\")").unwrap_err().to_string(),
+        "mismatched types in argument 2 of `<`: expected a number, found string literal `\"a\"`"
+    );
+    assert_eq!(
+        run("(< \"a\" 1)").unwrap_err().to_string(),
+        "mismatched types in argument 2 of `<`: expected a string literal, found number `1`"
+    );
+    // the types are checked even once the result is known
+    assert_eq!(
+        run("(< 2 1 \"a\")").unwrap_err().to_string(),
+        "mismatched types in argument 3 of `<`: expected a number, found string literal `\"a\"`"
+    );
+    assert!(run("(< 'a)").is_err());
+    assert!(run("(<)").is_err());
+}
+
+// NaN is not ordered, so no comparison holds for it, not even with itself
+#[test]
+fn no_comparison_holds_for_nan() {
+    for comparison in ["=", "<", ">", "<=", ">="] {
+        assert_eq!(eval(&format!("({} +nan.0 1)", comparison)), "#f");
+        assert_eq!(eval(&format!("({} +nan.0 +nan.0)", comparison)), "#f");
+    }
+}
+
+#[test]
+fn eq_is_identity_and_equal_compares_contents() {
+    assert_eq!(eval("(equal? (list 1 2) (list 1 2))"), "#t");
+    assert_eq!(eval("(eq? (list 1) (list 1))"), "#f");
+    assert_eq!(eval("(eq? 1 1)"), "#t");
+    assert_eq!(eval("(equal? \"a\" \"a\")"), "#t");
+    assert_eq!(eval("(equal? 1 \"1\")"), "#f");
+}
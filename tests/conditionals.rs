@@ -0,0 +1,44 @@
+mod common;
+
+use common::eval;
+
+// only nil, which is also the empty list, and #f are false
+#[test]
+fn only_nil_and_false_are_false() {
+    assert_eq!(eval("(if nil 1 2)"), "2");
+    assert_eq!(eval("(if '() 1 2)"), "2");
+    assert_eq!(eval("(if #f 1 2)"), "2");
+    assert_eq!(eval("(if 0 1 2)"), "1");
+    assert_eq!(eval("(if \"\" 1 2)"), "1");
+    assert_eq!(eval("(not nil)"), "#t");
+    assert_eq!(eval("(not 0)"), "#f");
+}
+
+// the branches not taken would fail if they were reduced
+#[test]
+fn conditionals_only_reduce_the_branch_taken() {
+    assert_eq!(eval("(if #t 1 (car 1))"), "1");
+    assert_eq!(eval("(if #f (car 1))"), "nil");
+    assert_eq!(eval("(cond (#f (car 1)) (#t 2) ((car 1) 3))"), "2");
+    assert_eq!(eval("(when #f (car 1))"), "nil");
+    assert_eq!(eval("(unless #t (car 1))"), "nil");
+    assert_eq!(eval("(when 1 2 3)"), "3");
+    assert_eq!(eval("(unless nil 4)"), "4");
+}
+
+#[test]
+fn and_and_or_stop_at_the_first_deciding_value() {
+    assert_eq!(eval("(and)"), "#t");
+    assert_eq!(eval("(or)"), "#f");
+    assert_eq!(eval("(and 1 2)"), "2");
+    assert_eq!(eval("(or #f 3)"), "3");
+    assert_eq!(eval("(and #f (car 1))"), "#f");
+    assert_eq!(eval("(or 1 (car 1))"), "1");
+}
+
+#[test]
+fn cond_clauses_without_a_body_give_their_test() {
+    assert_eq!(eval("(cond (#f 1) ((+ 1 2)))"), "3");
+    assert_eq!(eval("(cond (#f 1) (else 2))"), "2");
+    assert_eq!(eval("(cond (#f 1))"), "nil");
+}
@@ -0,0 +1,92 @@
+mod common;
+
+use atlas::Parser;
+use common::Buffer;
+
+#[test]
+fn errors_render_with_the_line_they_happened_in() {
+    let mut parser = Parser::new();
+    parser.set_output(Buffer::default());
+    parser.parse("(print 1)\n(+ 1 \"a\")".to_string()).unwrap();
+    let err = parser.reduce_all().unwrap_err();
+    assert_eq!(
+        parser.render_error(&err, "test.atl"),
+        "error: mismatched types in argument 2 of `+`\n \
+         --> test.atl:2:1\n  \
+         |\n\
+         2 | (+ 1 \"a\")\n  \
+         | ^^^^^^^^^\n  \
+         = note: expected a number, found string literal `\"a\"`\n"
+    );
+}
+
+// functions keep pointing at the source code they were parsed from, which used to be replaced
+// by the next parsed one, panicking when the old span fell inside one of its characters
+#[test]
+fn errors_in_functions_from_earlier_sources_point_at_them() {
+    let mut parser = Parser::new();
+    parser.set_output(Buffer::default());
+    parser.parse("(defun f () (car 1))".to_string()).unwrap();
+    parser.reduce_all().unwrap();
+    parser.parse("\"ééééééé\" (f)".to_string()).unwrap();
+    let err = parser.reduce_all().unwrap_err();
+    let rendered = parser.render_error(&err, "<expression>");
+    assert!(
+        rendered.contains("1 | (defun f () (car 1))"),
+        "{}",
+        rendered
+    );
+    assert!(rendered.contains("<expression>:1:13"), "{}", rendered);
+}
+
+#[test]
+fn spans_outside_the_source_are_left_out() {
+    let mut parser = Parser::new();
+    parser.parse("(car 1)".to_string()).unwrap();
+    let err = parser.reduce_all().unwrap_err();
+    assert_eq!(
+        err.render("é", "test.atl"),
+        "error: mismatched types in argument 1 of `car`\n \
+         --> test.atl\n \
+         = note: expected a pair, found number `1`\n"
+    );
+}
+
+// the previous tree used to stay in place, so reducing after a failed parse ran it again
+#[test]
+fn failed_parses_leave_nothing_to_reduce() {
+    let buffer = Buffer::default();
+    let mut parser = Parser::new();
+    parser.set_output(buffer.clone());
+    parser.parse("(print \"side effect\")".to_string()).unwrap();
+    parser.reduce_all().unwrap();
+    assert!(parser.parse("(oops".to_string()).is_err());
+    assert_eq!(parser.reduce_all().unwrap(), None);
+    assert_eq!(buffer.contents(), "side effect\n");
+}
+
+// lines used to end only at "\n", unlike in the lexer
+#[test]
+fn errors_count_lines_the_way_the_lexer_does() {
+    for (source, line) in [
+        ("(print 1)\r(car 1)", 2),
+        ("(print 1)\u{2028}(car 1)", 2),
+        ("(print 1)\r\n\r\n(car 1)", 3),
+    ] {
+        let mut parser = Parser::new();
+        parser.set_output(Buffer::default());
+        parser.parse(source.to_string()).unwrap();
+        let err = parser.reduce_all().unwrap_err();
+        let rendered = parser.render_error(&err, "test.atl");
+        assert!(
+            rendered.contains(&format!("--> test.atl:{}:1\n", line)),
+            "{:?}",
+            rendered
+        );
+        assert!(
+            rendered.contains(&format!("{} | (car 1)\n", line)),
+            "{:?}",
+            rendered
+        );
+    }
+}
@@ -0,0 +1,38 @@
+mod common;
+
+use common::eval;
+
+// they used to return the name as a string, printed with quotes
+#[test]
+fn definitions_return_their_name_as_a_symbol() {
+    assert_eq!(eval("(defun another (test))"), "another");
+    assert_eq!(eval("(define x 1)"), "x");
+    assert_eq!(eval("(eq? (define x 1) 'x)"), "#t");
+}
+
+// sibling expressions at the same depth used to be mixed up
+#[test]
+fn nested_expressions_reduce_in_place() {
+    assert_eq!(eval("(+ (+ 1 2) (* 3 (+ 1 1)) 4)"), "13");
+}
+
+// tail calls are reduced in a loop, so this doesn't grow the stack, nor hit the depth limit
+#[test]
+fn tail_recursion_runs_for_millions_of_iterations() {
+    assert_eq!(
+        eval("(defun loop (n) (if (= n 0) 0 (loop (- n 1)))) (loop 1000000)"),
+        "0"
+    );
+}
+
+// every let* binding gets a frame of its own, which used to be looked up and dropped
+// recursively, overflowing the stack
+#[test]
+fn let_star_takes_any_number_of_bindings() {
+    let bindings: Vec<String> = (0..200000).map(|i| format!("(x{} {})", i, i)).collect();
+    let source = format!(
+        "(let* ({}) (set! x0 1) (defun last () x199999) (+ x0 (last)))",
+        bindings.join(" ")
+    );
+    assert_eq!(eval(&source), "200000");
+}
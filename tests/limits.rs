@@ -0,0 +1,71 @@
+mod common;
+
+use std::thread;
+
+use atlas::{error::AtlasError, Parser, DEFAULT_MAX_DEPTH, DEFAULT_STACK_SIZE};
+use common::{eval, run};
+
+// tests run on threads with the default stack size, so these would overflow it if the default
+// limit were too high for it
+#[test]
+fn deep_recursion_hits_the_default_limit() {
+    let err =
+        run("(defun deep (n) (if (= n 0) 0 (+ 1 (deep (- n 1))))) (deep 100000)").unwrap_err();
+    assert!(
+        matches!(err, AtlasError::RecursionLimit { limit, .. } if limit == DEFAULT_MAX_DEPTH),
+        "unexpected error: {:?}",
+        err
+    );
+}
+
+#[test]
+fn deeply_nested_source_hits_the_default_limit() {
+    let source = format!("{}{}", "(".repeat(100000), ")".repeat(100000));
+    let err = run(&source).unwrap_err();
+    assert!(
+        matches!(err, AtlasError::RecursionLimit { limit, .. } if limit == DEFAULT_MAX_DEPTH),
+        "unexpected error: {:?}",
+        err
+    );
+}
+
+#[test]
+fn recursion_below_the_limit_is_fine() {
+    assert_eq!(
+        eval("(defun deep (n) (if (= n 0) 0 (+ 1 (deep (- n 1))))) (deep 100)"),
+        "100"
+    );
+}
+
+#[test]
+fn the_limit_can_be_changed() {
+    let mut parser = Parser::new();
+    parser.set_max_depth(10);
+    parser
+        .parse("(defun deep (n) (if (= n 0) 0 (+ 1 (deep (- n 1))))) (deep 20)".to_string())
+        .unwrap();
+    let err = parser.reduce_all().unwrap_err();
+    assert!(
+        matches!(err, AtlasError::RecursionLimit { limit: 10, .. }),
+        "unexpected error: {:?}",
+        err
+    );
+}
+
+// every level of a template used to take more stack than the limit allowed for, so recursing
+// through an unquote overflowed the stack before reaching it
+#[test]
+fn recursion_through_quasiquote_hits_the_default_limit() {
+    let source = "(defun f (n) (if (= n 0) 0 `(a . ,(f (- n 1))))) (f 1000)";
+    let err = thread::Builder::new()
+        .stack_size(DEFAULT_STACK_SIZE)
+        .spawn(move || run(source).unwrap_err())
+        .unwrap()
+        .join()
+        .unwrap();
+    assert!(
+        matches!(err, AtlasError::RecursionLimit { limit, .. } if limit == DEFAULT_MAX_DEPTH),
+        "unexpected error: {:?}",
+        err
+    );
+}
@@ -0,0 +1,42 @@
+mod common;
+
+use common::eval;
+
+#[test]
+fn lists_print_with_dots_only_for_improper_tails() {
+    assert_eq!(eval("(list 1 2 3)"), "(1 2 3)");
+    assert_eq!(eval("(cons 1 2)"), "(1 . 2)");
+    assert_eq!(eval("(cons 1 (cons 2 3))"), "(1 2 . 3)");
+    assert_eq!(eval("(list (list 1) nil)"), "((1) nil)");
+}
+
+#[test]
+fn eq_compares_pairs_by_identity() {
+    assert_eq!(eval("(define a (list 1 2)) (eq? a a)"), "#t");
+    assert_eq!(eval("(eq? (list 1 2) (list 1 2))"), "#f");
+    assert_eq!(eval("(equal? (list 1 2) (list 1 2))"), "#t");
+}
+
+// lists nested through car used to be dropped, compared and printed recursively, overflowing
+// the stack
+#[test]
+fn deeply_nested_lists_dont_overflow_the_stack() {
+    let nest = "(defun nest (n acc) (if (= n 0) acc (nest (- n 1) (list acc))))";
+    assert_eq!(eval(&format!("{} (length (nest 100000 nil))", nest)), "1");
+    assert_eq!(
+        eval(&format!(
+            "{} (equal? (nest 100000 nil) (nest 100000 nil))",
+            nest
+        )),
+        "#t"
+    );
+    assert_eq!(
+        eval(&format!("{} (nest 100000 1)", nest)),
+        format!("{}1{}", "(".repeat(100000), ")".repeat(100000))
+    );
+    let long = "(defun long (n acc) (if (= n 0) acc (long (- n 1) (cons n acc))))";
+    assert_eq!(
+        eval(&format!("{} (length (long 100000 nil))", long)),
+        "100000"
+    );
+}
@@ -0,0 +1,41 @@
+mod common;
+
+use common::eval;
+
+#[test]
+fn integers_can_be_written_in_other_bases() {
+    assert_eq!(eval("#x1F"), "31");
+    assert_eq!(eval("#b101"), "5");
+    assert_eq!(eval("#o17"), "15");
+    assert_eq!(eval("0x10"), "16");
+    assert_eq!(eval("-#x10"), "-16");
+}
+
+#[test]
+fn rationals_are_kept_in_their_lowest_terms() {
+    assert_eq!(eval("1/3"), "1/3");
+    assert_eq!(eval("-2/4"), "-1/2");
+    assert_eq!(eval("4/2"), "2");
+    assert_eq!(eval("(exact? 1/3)"), "#t");
+}
+
+#[test]
+fn floats_can_have_exponents_and_no_integer_part() {
+    assert_eq!(eval("1e3"), "1000.0");
+    assert_eq!(eval("1.5e-3"), "0.0015");
+    assert_eq!(eval("2E+2"), "200.0");
+    assert_eq!(eval(".5"), "0.5");
+    assert_eq!(eval("-.5"), "-0.5");
+    assert_eq!(eval("+inf.0"), "+inf.0");
+    assert_eq!(eval("(inexact? .5)"), "#t");
+}
+
+// a lone dot, or more than one sign, used to be read as a number by the float parser
+#[test]
+fn names_that_look_like_numbers_are_symbols() {
+    for name in [
+        ".", "...", "--1", "-+1", "+-1", "1e+-3", "1.2.3", "inf", "nan", "1/0",
+    ] {
+        assert_eq!(eval(&format!("(symbol? '{})", name)), "#t", "{}", name);
+    }
+}
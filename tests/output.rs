@@ -0,0 +1,71 @@
+mod common;
+
+use std::{cell::Cell, io::Write, rc::Rc};
+
+use atlas::Parser;
+use common::Buffer;
+
+#[test]
+fn print_display_and_newline_write_to_the_output() {
+    let buffer = Buffer::default();
+    let mut parser = Parser::new();
+    parser.set_output(buffer.clone());
+    parser
+        .parse("(print \"a\" 1 (list 2)) (display \"b\" 3) (newline)".to_string())
+        .unwrap();
+    parser.reduce_all().unwrap();
+    assert_eq!(buffer.contents(), "a 1 (2)\nb3\n");
+}
+
+// closures capture their environment, which used to keep the output it was created with
+#[test]
+fn changing_the_output_applies_to_functions_defined_before() {
+    let mut parser = Parser::new();
+    parser.set_output(Buffer::default());
+    parser
+        .parse("(defun hi () (print \"from hi\"))".to_string())
+        .unwrap();
+    parser.reduce_all().unwrap();
+    let buffer = Buffer::default();
+    parser.set_output(buffer.clone());
+    parser.parse("(hi)".to_string()).unwrap();
+    parser.reduce_all().unwrap();
+    assert_eq!(buffer.contents(), "from hi\n");
+}
+
+// a writer telling when it is dropped
+struct Dropped(Rc<Cell<bool>>);
+
+impl Write for Dropped {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for Dropped {
+    fn drop(&mut self) {
+        self.0.set(true);
+    }
+}
+
+// functions hold the environment they are defined in, which used to keep it alive forever
+#[test]
+fn dropping_the_parser_drops_the_output() {
+    for source in [
+        "(+ 1 2)",
+        "(defun f (x) x)",
+        "(define g (lambda () (g)))",
+        "(letrec ((h (lambda () (h)))) h)",
+    ] {
+        let dropped = Rc::new(Cell::new(false));
+        let mut parser = Parser::new();
+        parser.set_output(Dropped(dropped.clone()));
+        parser.parse(source.to_string()).unwrap();
+        parser.reduce_all().unwrap();
+        drop(parser);
+        assert!(dropped.get(), "the output of `{}` was not dropped", source);
+    }
+}
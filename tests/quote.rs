@@ -0,0 +1,73 @@
+mod common;
+
+use common::{eval, run};
+
+#[test]
+fn quoted_code_is_kept_as_data() {
+    assert_eq!(eval("(quote (a \"b\" 1 #t))"), "(a \"b\" 1 #t)");
+    assert_eq!(eval("'(a (b c))"), "(a (b c))");
+    assert_eq!(eval("(symbol? 'a)"), "#t");
+    assert_eq!(eval("''a"), "(quote a)");
+}
+
+#[test]
+fn dotted_lists_can_be_quoted() {
+    assert_eq!(eval("'(a . b)"), "(a . b)");
+    assert_eq!(eval("'(a b . (c d))"), "(a b c d)");
+    assert_eq!(eval("'(a . #;x b)"), "(a . b)");
+}
+
+// these used to be read as lists holding a . symbol
+#[test]
+fn dots_anywhere_but_before_the_last_item_are_rejected() {
+    for (source, column) in [
+        ("'(a . b c)", 4),
+        ("'(a . . b)", 6),
+        ("'(. b)", 2),
+        ("'(a .)", 4),
+    ] {
+        let err = run(source).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unexpected `.`, a dot can only come before the last item of a list",
+            "{}",
+            source
+        );
+        assert_eq!(err.span(), Some((column, column + 1)), "{}", source);
+    }
+}
+
+#[test]
+fn quasiquote_reduces_only_what_is_unquoted() {
+    assert_eq!(eval("`(1 ,(+ 1 1) (+ 1 2))"), "(1 2 (+ 1 2))");
+    assert_eq!(eval("`(1 . ,(+ 1 1))"), "(1 . 2)");
+    assert_eq!(
+        eval("(quasiquote (1 (unquote (+ 1 1)) (unquote-splicing (list 3 4))))"),
+        "(1 2 3 4)"
+    );
+}
+
+#[test]
+fn unquote_splicing_inserts_the_items_of_a_list() {
+    assert_eq!(eval("`(1 ,@(list 2 3) 4)"), "(1 2 3 4)");
+    assert_eq!(eval("`(1 ,@nil 2)"), "(1 2)");
+    assert_eq!(eval("`(,@(list 1 2) . 3)"), "(1 2 . 3)");
+    assert!(run("`(1 ,@2)").is_err());
+    assert_eq!(
+        run("`,@(list 1)").unwrap_err().to_string(),
+        "unquote-splicing must be used inside a list"
+    );
+}
+
+// only the unquotes as deep as the outermost quasiquote are reduced
+#[test]
+fn nested_quasiquotes_keep_their_own_unquotes() {
+    assert_eq!(
+        eval("`(a `(b ,(c ,(+ 1 2))))"),
+        "(a (quasiquote (b (unquote (c 3)))))"
+    );
+    assert_eq!(
+        eval("`(a `(b ,(+ 1 2)))"),
+        "(a (quasiquote (b (unquote (+ 1 2)))))"
+    );
+}
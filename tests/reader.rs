@@ -0,0 +1,41 @@
+use atlas::{error::AtlasError, Parser};
+
+// parse some source code, expecting it to be rejected
+fn parse_error(source: &str) -> AtlasError {
+    Parser::new().parse(source.to_string()).unwrap_err()
+}
+
+#[test]
+fn unclosed_parentheses_point_at_the_opening_one() {
+    let err = parse_error("(print 1)\n(+ 1 (* 2 3)");
+    assert_eq!(err.to_string(), "unclosed delimiter `(`");
+    assert_eq!(err.span(), Some((10, 11)));
+}
+
+#[test]
+fn stray_closing_parentheses_point_at_themselves() {
+    let err = parse_error("(+ 1 2) )");
+    assert_eq!(err.to_string(), "unexpected closing delimiter `)`");
+    assert_eq!(err.span(), Some((8, 9)));
+}
+
+#[test]
+fn unterminated_strings_point_at_their_opening_quote() {
+    let err = parse_error("(print \"abc)");
+    assert_eq!(err.to_string(), "unterminated string literal");
+    assert_eq!(err.span(), Some((7, 8)));
+}
+
+#[test]
+fn reader_errors_render_with_their_position() {
+    let mut parser = Parser::new();
+    let err = parser.parse("(print 1)\n  (+ 1".to_string()).unwrap_err();
+    assert_eq!(
+        parser.render_error(&err, "test.atl"),
+        "error: unclosed delimiter `(`\n \
+         --> test.atl:2:3\n  \
+         |\n\
+         2 |   (+ 1\n  \
+         |   ^\n"
+    );
+}
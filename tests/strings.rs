@@ -0,0 +1,72 @@
+mod common;
+
+use atlas::{error::AtlasError, Parser};
+use common::{eval, Buffer};
+
+// display a string literal, getting it back as written to the output
+fn displayed(literal: &str) -> String {
+    let buffer = Buffer::default();
+    let mut parser = Parser::new();
+    parser.set_output(buffer.clone());
+    parser.parse(format!("(display {})", literal)).unwrap();
+    parser.reduce_all().unwrap();
+    buffer.contents()
+}
+
+// parse some source code, expecting it to be rejected
+fn parse_error(source: &str) -> AtlasError {
+    Parser::new().parse(source.to_string()).unwrap_err()
+}
+
+#[test]
+fn strings_keep_whitespace_parentheses_and_comments() {
+    assert_eq!(
+        displayed("\"a b\t (c) ; d #| e |#\""),
+        "a b\t (c) ; d #| e |#"
+    );
+    assert_eq!(eval("(list \"(\" \")\")"), "(\"(\" \")\")");
+}
+
+#[test]
+fn escape_sequences_are_decoded() {
+    assert_eq!(displayed(r#""a\nb""#), "a\nb");
+    assert_eq!(displayed(r#""a\tb""#), "a\tb");
+    assert_eq!(displayed(r#""a\rb""#), "a\rb");
+    assert_eq!(displayed(r#""a\0b""#), "a\0b");
+    assert_eq!(displayed(r#""say \"hi\"""#), "say \"hi\"");
+    assert_eq!(displayed(r#""back\\slash""#), "back\\slash");
+    assert_eq!(displayed(r#""\u{e9}\u{1F600}""#), "é😀");
+}
+
+#[test]
+fn unknown_escapes_point_at_themselves() {
+    let err = parse_error(r#"(print "a\qb")"#);
+    assert_eq!(err.to_string(), "unknown character escape `\\q`");
+    assert_eq!(err.span(), Some((9, 11)));
+}
+
+#[test]
+fn invalid_unicode_escapes_point_at_themselves() {
+    let err = parse_error(r#""\u{110000}""#);
+    assert_eq!(err.to_string(), "invalid unicode character escape");
+    assert_eq!(err.span(), Some((1, 11)));
+    let err = parse_error(r#""\u{1234567}""#);
+    assert_eq!(err.to_string(), "invalid unicode character escape");
+    for source in [r#""\u12""#, r#""\u{12""#] {
+        let err = parse_error(source);
+        assert_eq!(
+            err.to_string(),
+            "unicode escapes must be written as \\u{...}"
+        );
+        assert_eq!(err.span().map(|span| span.0), Some(1), "{}", source);
+    }
+}
+
+#[test]
+fn unterminated_strings_point_at_their_opening_quote() {
+    for source in ["(print \"abc\\", "(print \"a\nb"] {
+        let err = parse_error(source);
+        assert_eq!(err.to_string(), "unterminated string literal");
+        assert_eq!(err.span(), Some((7, 8)), "{:?}", source);
+    }
+}
@@ -0,0 +1,37 @@
+mod common;
+
+use common::{eval, run};
+
+#[test]
+fn only_symbols_are_symbols() {
+    assert_eq!(eval("(symbol? 'a)"), "#t");
+    assert_eq!(eval("(symbol? \"a\")"), "#f");
+    assert_eq!(eval("(symbol? 1)"), "#f");
+    assert_eq!(eval("(symbol? '(a))"), "#f");
+}
+
+#[test]
+fn symbols_convert_to_and_from_strings() {
+    assert_eq!(eval("(symbol->string 'abc)"), "\"abc\"");
+    assert_eq!(eval("(string->symbol \"abc\")"), "abc");
+    // any string names a symbol, even one that would be read as something else
+    assert_eq!(eval("(symbol? (string->symbol \"1\"))"), "#t");
+    assert_eq!(
+        run("(symbol->string \"abc\")").unwrap_err().to_string(),
+        "mismatched types in argument 1 of `symbol->string`: expected a symbol, found string literal `\"abc\"`"
+    );
+    assert!(run("(string->symbol 'abc)").is_err());
+}
+
+// symbols with the same name are the same symbol, however they were made
+#[test]
+fn symbols_are_interned() {
+    assert_eq!(eval("(eq? 'abc 'abc)"), "#t");
+    assert_eq!(eval("(eq? (string->symbol \"abc\") 'abc)"), "#t");
+    assert_eq!(
+        eval("(eq? (string->symbol (symbol->string 'abc)) 'abc)"),
+        "#t"
+    );
+    assert_eq!(eval("(eq? 'abc 'abd)"), "#f");
+    assert_eq!(eval("(eq? 'abc \"abc\")"), "#f");
+}